@@ -0,0 +1,13 @@
+//! Append log messages to a file.
+//!
+//! ```shell
+//! cargo run --example file && cat file_example.log
+//! ```
+use simple_logger::SimpleLogger;
+
+fn main() {
+    SimpleLogger::new().with_output_file("file_example.log").init().unwrap();
+
+    log::warn!("This is an example message.");
+    log::logger().flush();
+}
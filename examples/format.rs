@@ -0,0 +1,11 @@
+//! Use a custom function to lay out each line.
+use simple_logger::SimpleLogger;
+
+fn main() {
+    SimpleLogger::new()
+        .with_format(|w, ctx, record| write!(w, "{} {}: {}", ctx.level(), ctx.target(), record.args()))
+        .init()
+        .unwrap();
+
+    log::warn!("This is an example message.");
+}
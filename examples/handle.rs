@@ -0,0 +1,15 @@
+use log::LevelFilter;
+use simple_logger::SimpleLogger;
+
+fn main() {
+    let handle = SimpleLogger::new()
+        .with_level(LevelFilter::Info)
+        .init_with_handle()
+        .unwrap();
+
+    log::debug!("This will NOT be logged.");
+
+    handle.set_level(LevelFilter::Debug);
+
+    log::debug!("This will be logged.");
+}
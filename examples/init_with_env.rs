@@ -1,18 +1,22 @@
 //! Set the `RUST_LOG` environment variable and run this example to see the output change.
 //!
-//! Valid values are `OFF`, `ERROR`, `WARN`, `INFO`, `DEBUG`, and `TRACE`.
+//! Valid levels are `OFF`, `ERROR`, `WARN`, `INFO`, `DEBUG`, and `TRACE`.
 //!
 //! ```shell
 //! RUST_LOG=WARN cargo run --example init_with_env
 //! ```
 //!
+//! Levels for specific modules can be set with comma-separated `target=level` directives:
+//!
+//! ```shell
+//! RUST_LOG=WARN,init_with_env=INFO cargo run --example init_with_env
+//! ```
+//!
 //! It should also work if the environment variable is not set:
 //!
 //! ```shell
 //! cargo run --example init_with_env
 //! ```
-use simple_logger;
-
 fn main() {
     simple_logger::init_with_env().unwrap();
 
@@ -0,0 +1,7 @@
+use simple_logger::SimpleLogger;
+
+fn main() {
+    SimpleLogger::new().with_json().init().unwrap();
+
+    log::warn!("This is an example message.");
+}
@@ -0,0 +1,14 @@
+//! Errors and warnings are written to STDERR, all other messages to STDOUT.
+//!
+//! ```shell
+//! cargo run --example split 2>/dev/null
+//! ```
+use simple_logger::{Output, SimpleLogger};
+
+fn main() {
+    SimpleLogger::new().with_output(Output::Split).init().unwrap();
+
+    log::error!("This is written to STDERR.");
+    log::warn!("This is written to STDERR.");
+    log::info!("This is written to STDOUT.");
+}
@@ -1,7 +1,11 @@
-use simple_logger::SimpleLogger;
+use simple_logger::{Output, SimpleLogger};
 
 fn main() {
-    SimpleLogger::new().with_colors(true).init().unwrap();
+    SimpleLogger::new()
+        .with_colors(true)
+        .with_output(Output::Stderr)
+        .init()
+        .unwrap();
 
     log::warn!("This is an example message.");
 }
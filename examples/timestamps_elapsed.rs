@@ -0,0 +1,9 @@
+use simple_logger::SimpleLogger;
+
+fn main() {
+    SimpleLogger::new().with_elapsed_timestamps().init().unwrap();
+
+    log::warn!("This is an example message showing the time since the logger was initialised.");
+    std::thread::sleep(std::time::Duration::from_millis(250));
+    log::info!("This message was logged about 250ms later.");
+}
@@ -0,0 +1,8 @@
+use simple_logger::SimpleLogger;
+
+fn main() {
+    SimpleLogger::new().with_timezone("Europe/London").init().unwrap();
+
+    log::warn!("This is an example message using a named timezone.");
+    log::info!("Daylight savings changes will be respected.");
+}
@@ -0,0 +1,133 @@
+//! Collapsing consecutive identical messages.
+
+use log::{Level, Record};
+use std::sync::{Mutex, PoisonError};
+
+/// Drops records that repeat the previous record, counting them.
+///
+/// See [`SimpleLogger::with_dedup`](crate::SimpleLogger::with_dedup).
+#[derive(Default)]
+pub(crate) struct Deduplicator {
+    last: Mutex<Option<Last>>,
+}
+
+/// The last record that was logged.
+struct Last {
+    level: Level,
+    target: String,
+    message: String,
+    module_path: Option<String>,
+    file: Option<String>,
+    line: Option<u32>,
+
+    /// The number of times it has been repeated since it was logged or last summarized.
+    repeated: usize,
+}
+
+/// A summary of the repeats of a record.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Repeated {
+    pub(crate) count: usize,
+    pub(crate) level: Level,
+    pub(crate) target: String,
+    pub(crate) module_path: Option<String>,
+    pub(crate) file: Option<String>,
+    pub(crate) line: Option<u32>,
+}
+
+impl Deduplicator {
+    /// Check if a record is different to the previous record, and should be logged.
+    ///
+    /// Also returns a summary if the previous record was repeated, which should be logged before the record.
+    pub(crate) fn check(&self, record: &Record) -> (bool, Option<Repeated>) {
+        let mut last = self.last.lock().unwrap_or_else(PoisonError::into_inner);
+        let message = record.args().to_string();
+
+        if let Some(last) = last.as_mut() {
+            if last.level == record.level() && last.target == record.target() && last.message == message {
+                last.repeated += 1;
+                return (false, None);
+            }
+        }
+
+        let repeated = last.as_mut().and_then(Last::summarize);
+
+        *last = Some(Last {
+            level: record.level(),
+            target: record.target().to_string(),
+            message,
+            module_path: record.module_path().map(str::to_string),
+            file: record.file().map(str::to_string),
+            line: record.line(),
+            repeated: 0,
+        });
+
+        (true, repeated)
+    }
+
+    /// A summary of the repeats of the last record, if it has been repeated since it was logged or last summarized.
+    pub(crate) fn flush(&self) -> Option<Repeated> {
+        let mut last = self.last.lock().unwrap_or_else(PoisonError::into_inner);
+        last.as_mut().and_then(Last::summarize)
+    }
+}
+
+impl Last {
+    fn summarize(&mut self) -> Option<Repeated> {
+        if self.repeated == 0 {
+            return None;
+        }
+
+        Some(Repeated {
+            count: std::mem::take(&mut self.repeated),
+            level: self.level,
+            target: self.target.clone(),
+            module_path: self.module_path.clone(),
+            file: self.file.clone(),
+            line: self.line,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check(dedup: &Deduplicator, level: Level, message: &str) -> (bool, Option<usize>) {
+        let (allowed, repeated) = dedup.check(
+            &Record::builder()
+                .args(format_args!("{}", message))
+                .level(level)
+                .target("my_crate")
+                .build(),
+        );
+
+        (allowed, repeated.map(|repeated| repeated.count))
+    }
+
+    #[test]
+    fn test_dedup() {
+        let dedup = Deduplicator::default();
+
+        assert_eq!(check(&dedup, Level::Warn, "retrying"), (true, None));
+        assert_eq!(check(&dedup, Level::Warn, "retrying"), (false, None));
+        assert_eq!(check(&dedup, Level::Warn, "retrying"), (false, None));
+        assert_eq!(check(&dedup, Level::Error, "retrying"), (true, Some(2)));
+        assert_eq!(check(&dedup, Level::Error, "giving up"), (true, None));
+    }
+
+    #[test]
+    fn test_dedup_flush() {
+        let dedup = Deduplicator::default();
+
+        assert_eq!(check(&dedup, Level::Warn, "retrying"), (true, None));
+        assert_eq!(dedup.flush(), None);
+        assert_eq!(check(&dedup, Level::Warn, "retrying"), (false, None));
+        assert_eq!(dedup.flush().map(|repeated| repeated.count), Some(1));
+        assert_eq!(dedup.flush(), None);
+
+        // Repeats are still collapsed after flushing.
+        assert_eq!(check(&dedup, Level::Warn, "retrying"), (false, None));
+        assert_eq!(check(&dedup, Level::Warn, "done"), (true, Some(1)));
+    }
+}
@@ -0,0 +1,351 @@
+//! Writing log messages to a file.
+
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, LineWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+#[cfg(feature = "timestamps")]
+use time::{format_description::FormatItem, OffsetDateTime};
+
+#[cfg(feature = "timestamps")]
+const PERIOD_FORMAT_HOURLY: &[FormatItem] = time::macros::format_description!("[year]-[month]-[day]-[hour]");
+
+#[cfg(feature = "timestamps")]
+const PERIOD_FORMAT_DAILY: &[FormatItem] = time::macros::format_description!("[year]-[month]-[day]");
+
+/// When to start a new log file.
+///
+/// See [`SimpleLogger::with_file_rotation`](crate::SimpleLogger::with_file_rotation).
+///
+/// Some variants depend on optional features, so matching on this enum needs a
+/// wildcard arm.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Rotation {
+    /// Always write to the same file.
+    #[default]
+    Never,
+    /// Start a new file when writing a message would take the file over
+    /// `max_bytes`.
+    ///
+    /// The previous files are renamed to `<path>.1`, `<path>.2`, etc. (with
+    /// `<path>.1` being the most recent), and only `keep` of them are kept.
+    Size { max_bytes: u64, keep: usize },
+    /// Start a new file every hour, named like `app.2026-10-16-14.log` for a
+    /// path of `app.log`.
+    ///
+    /// This variant is only available if the `timestamps` feature is enabled.
+    #[cfg(feature = "timestamps")]
+    Hourly,
+    /// Start a new file every day, named like `app.2026-10-16.log` for a path
+    /// of `app.log`.
+    ///
+    /// This variant is only available if the `timestamps` feature is enabled.
+    #[cfg(feature = "timestamps")]
+    Daily,
+}
+
+impl Rotation {
+    /// The period a message logged at `now` belongs to, used to name the file it is written to.
+    ///
+    /// Returns `None` if files aren't rotated by time.
+    #[cfg(feature = "timestamps")]
+    pub(crate) fn period(self, now: OffsetDateTime) -> Option<String> {
+        let format = match self {
+            Rotation::Hourly => PERIOD_FORMAT_HOURLY,
+            Rotation::Daily => PERIOD_FORMAT_DAILY,
+            _ => return None,
+        };
+
+        now.format(format).ok()
+    }
+}
+
+/// A file that log messages are appended to.
+///
+/// The file is opened by [`FileSink::open`], which is called from
+/// [`SimpleLogger::init`](crate::SimpleLogger::init) so that errors can be
+/// reported. If the logger is used without calling `init()`, the file is
+/// opened when the first message is written.
+pub(crate) struct FileSink {
+    path: PathBuf,
+    state: Mutex<State>,
+}
+
+/// The open file, guarded by the lock in [`FileSink`] so that rotation and writes never interleave.
+#[derive(Default)]
+struct State {
+    writer: Option<LineWriter<File>>,
+
+    /// The period the open file was named for, when rotating files by time.
+    period: Option<String>,
+
+    /// The number of bytes in the open file.
+    size: u64,
+}
+
+impl FileSink {
+    pub(crate) fn new(path: PathBuf) -> FileSink {
+        FileSink {
+            path,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Open the file for the given period if it isn't already open.
+    pub(crate) fn open(&self, period: Option<&str>) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        state.switch_period(period)?;
+        state.open(&self.path)?;
+        Ok(())
+    }
+
+    /// Write a single line to the file, adding a newline.
+    ///
+    /// The file is rotated first if needed. When rotating files by time,
+    /// `period` is the period the message belongs to (see [`Rotation::period`]).
+    pub(crate) fn write_line(&self, line: &str, rotation: Rotation, period: Option<&str>) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let len = line.len() as u64 + 1;
+
+        state.switch_period(period)?;
+
+        if let Rotation::Size { max_bytes, keep } = rotation {
+            state.open(&self.path)?;
+
+            if state.size > 0 && state.size + len > max_bytes {
+                state.rotate(&self.path, keep)?;
+            }
+        }
+
+        writeln!(state.open(&self.path)?, "{}", line)?;
+        state.size += len;
+        Ok(())
+    }
+
+    pub(crate) fn flush(&self) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+
+        match state.writer.as_mut() {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl State {
+    fn open(&mut self, path: &Path) -> io::Result<&mut LineWriter<File>> {
+        if self.writer.is_none() {
+            let path = match &self.period {
+                Some(period) => dated(path, period),
+                None => path.to_path_buf(),
+            };
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            self.size = file.metadata()?.len();
+            self.writer = Some(LineWriter::new(file));
+        }
+
+        Ok(self.writer.as_mut().expect("the file was opened above"))
+    }
+
+    /// Close the file if it was opened for a different period.
+    ///
+    /// The file for the new period is opened by the next call to [`State::open`].
+    fn switch_period(&mut self, period: Option<&str>) -> io::Result<()> {
+        if self.period.as_deref() != period {
+            if let Some(mut writer) = self.writer.take() {
+                writer.flush()?;
+            }
+
+            self.period = period.map(str::to_string);
+        }
+
+        Ok(())
+    }
+
+    /// Close the file and shift it and the previous files along by one, deleting the oldest.
+    ///
+    /// The file is reopened by the next call to [`State::open`].
+    fn rotate(&mut self, path: &Path, keep: usize) -> io::Result<()> {
+        if let Some(mut writer) = self.writer.take() {
+            writer.flush()?;
+        }
+
+        if keep == 0 {
+            return remove_if_exists(path);
+        }
+
+        remove_if_exists(&numbered(path, keep))?;
+
+        for n in (1..keep).rev() {
+            rename_if_exists(&numbered(path, n), &numbered(path, n + 1))?;
+        }
+
+        rename_if_exists(path, &numbered(path, 1))
+    }
+}
+
+/// The path of a previous log file, e.g. `app.log.1`.
+fn numbered(path: &Path, n: usize) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(format!(".{}", n));
+    PathBuf::from(name)
+}
+
+/// The path of a log file for a period, e.g. `app.2026-10-16.log`.
+fn dated(path: &Path, period: &str) -> PathBuf {
+    let mut name = path.file_stem().map(OsString::from).unwrap_or_default();
+    name.push(".");
+    name.push(period);
+
+    if let Some(extension) = path.extension() {
+        name.push(".");
+        name.push(extension);
+    }
+
+    path.with_file_name(name)
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+fn rename_if_exists(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// A file in the temporary directory for tests, which is removed when it is dropped so that failing tests don't leave
+/// it behind.
+#[cfg(test)]
+pub(crate) struct TempFile(PathBuf);
+
+#[cfg(test)]
+impl TempFile {
+    /// A path that is unique to this test, with no file at it yet.
+    pub(crate) fn new() -> TempFile {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        let n = NEXT.fetch_add(1, Ordering::Relaxed);
+        TempFile::at(std::env::temp_dir().join(format!("simple_logger_{}_{}.log", std::process::id(), n)))
+    }
+
+    /// Remove the file at a path when the guard is dropped, e.g. a file created by rotation.
+    pub(crate) fn at(path: PathBuf) -> TempFile {
+        let _ = fs::remove_file(&path);
+        TempFile(path)
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// The contents of the file.
+    pub(crate) fn read(&self) -> String {
+        fs::read_to_string(&self.0).unwrap()
+    }
+}
+
+#[cfg(test)]
+impl AsRef<Path> for TempFile {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_write_line_appends() {
+        let file = TempFile::new();
+
+        let sink = FileSink::new(file.path().to_path_buf());
+        sink.open(None).unwrap();
+        sink.write_line("first", Rotation::Never, None).unwrap();
+        sink.flush().unwrap();
+
+        let sink = FileSink::new(file.path().to_path_buf());
+        sink.write_line("second", Rotation::Never, None).unwrap();
+        sink.flush().unwrap();
+
+        assert_eq!(file.read(), "first\nsecond\n");
+    }
+
+    #[test]
+    fn test_rotation_size() {
+        let file = TempFile::new();
+        let first = TempFile::at(numbered(file.path(), 1));
+        let second = TempFile::at(numbered(file.path(), 2));
+        let rotation = Rotation::Size { max_bytes: 8, keep: 2 };
+
+        let sink = FileSink::new(file.path().to_path_buf());
+        for line in ["one", "two", "three", "four", "five"] {
+            sink.write_line(line, rotation, None).unwrap();
+        }
+        sink.flush().unwrap();
+
+        assert_eq!(file.read(), "five\n");
+        assert_eq!(first.read(), "four\n");
+        assert_eq!(second.read(), "three\n");
+        assert!(!numbered(file.path(), 3).exists());
+    }
+
+    #[test]
+    fn test_dated() {
+        assert_eq!(
+            dated(Path::new("logs/app.log"), "2026-10-16"),
+            Path::new("logs/app.2026-10-16.log")
+        );
+        assert_eq!(dated(Path::new("app"), "2026-10-16"), Path::new("app.2026-10-16"));
+    }
+
+    #[test]
+    #[cfg(feature = "timestamps")]
+    fn test_rotation_period() {
+        let now = time::macros::datetime!(2026-10-16 14:30 +01:00);
+        assert_eq!(Rotation::Daily.period(now).as_deref(), Some("2026-10-16"));
+        assert_eq!(Rotation::Hourly.period(now).as_deref(), Some("2026-10-16-14"));
+        assert_eq!(Rotation::Never.period(now), None);
+    }
+
+    #[test]
+    #[cfg(feature = "timestamps")]
+    fn test_rotation_time() {
+        let file = TempFile::new();
+        let first = TempFile::at(dated(file.path(), "2026-10-16"));
+        let second = TempFile::at(dated(file.path(), "2026-10-17"));
+
+        let sink = FileSink::new(file.path().to_path_buf());
+        sink.write_line("one", Rotation::Daily, Some("2026-10-16")).unwrap();
+        sink.write_line("two", Rotation::Daily, Some("2026-10-16")).unwrap();
+        sink.write_line("three", Rotation::Daily, Some("2026-10-17")).unwrap();
+        sink.flush().unwrap();
+
+        assert_eq!(first.read(), "one\ntwo\n");
+        assert_eq!(second.read(), "three\n");
+        assert!(!file.path().exists());
+    }
+}
@@ -0,0 +1,324 @@
+//! Output formats other than the default text format.
+
+use log::Record;
+use std::{fmt::Write, io};
+
+/// A function that writes a complete line for a record.
+///
+/// See [`SimpleLogger::with_format`](crate::SimpleLogger::with_format).
+pub(crate) type FormatFn = dyn Fn(&mut dyn io::Write, &Context, &Record) -> io::Result<()> + Send + Sync;
+
+/// The parts of a line that [`SimpleLogger`](crate::SimpleLogger) has already rendered, passed to a custom format
+/// function.
+///
+/// See [`SimpleLogger::with_format`](crate::SimpleLogger::with_format).
+#[derive(Debug)]
+pub struct Context<'a> {
+    pub(crate) timestamp: Option<&'a str>,
+    pub(crate) level: &'a str,
+    pub(crate) target: &'a str,
+    pub(crate) thread: Option<&'a str>,
+}
+
+impl<'a> Context<'a> {
+    /// The formatted timestamp, or `None` if timestamps are disabled.
+    pub fn timestamp(&self) -> Option<&'a str> {
+        self.timestamp
+    }
+
+    /// The level, padded to 5 characters and colored if colors are enabled.
+    pub fn level(&self) -> &'a str {
+        self.level
+    }
+
+    /// The record's target, or its module path if the target is empty.
+    pub fn target(&self) -> &'a str {
+        self.target
+    }
+
+    /// The thread name, or `None` if thread names are disabled.
+    pub fn thread(&self) -> Option<&'a str> {
+        self.thread
+    }
+}
+
+/// A rendered timestamp, passed to structured formats.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Timestamp<'a> {
+    /// A date and time, e.g. `2026-10-16T12:00:00.000Z`.
+    Text(&'a str),
+    /// A number, e.g. the time since the Unix epoch, which is written without quotes.
+    Number(&'a str),
+}
+
+/// Render a record using a custom format function.
+///
+/// Returns `None` if the format function fails.
+pub(crate) fn custom(format: &FormatFn, context: &Context, record: &Record) -> Option<String> {
+    let mut buffer = Vec::new();
+    format(&mut buffer, context, record).ok()?;
+    Some(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+/// Render a record as a single-line JSON object.
+///
+/// The `thread` field is only included if thread names are enabled, and the
+/// `timestamp` field is only included if timestamps are enabled.
+pub(crate) fn json(timestamp: Option<Timestamp>, record: &Record, target: &str, thread: Option<&str>) -> String {
+    let mut object = JsonObject::default();
+
+    match timestamp {
+        Some(Timestamp::Text(timestamp)) => object.string("timestamp", timestamp),
+        Some(Timestamp::Number(timestamp)) => object.raw("timestamp", timestamp),
+        None => {}
+    }
+
+    object.string("level", &record.level().as_str().to_ascii_lowercase());
+    object.string("target", target);
+
+    if let Some(thread) = thread {
+        object.string("thread", thread);
+    }
+
+    match record.module_path() {
+        Some(module_path) => object.string("module_path", module_path),
+        None => object.null("module_path"),
+    }
+
+    match record.file() {
+        Some(file) => object.string("file", file),
+        None => object.null("file"),
+    }
+
+    match record.line() {
+        Some(line) => object.number("line", line),
+        None => object.null("line"),
+    }
+
+    object.string("message", &record.args().to_string());
+    object.finish()
+}
+
+/// Render a record as a line of [logfmt](https://brandur.org/logfmt) key/value pairs.
+///
+/// The `thread` field is only included if thread names are enabled, and the
+/// `ts` field is only included if timestamps are enabled.
+pub(crate) fn logfmt(timestamp: Option<&str>, record: &Record, target: &str, thread: Option<&str>) -> String {
+    let mut line = String::new();
+
+    if let Some(timestamp) = timestamp {
+        write_logfmt_pair(&mut line, "ts", timestamp);
+    }
+
+    write_logfmt_pair(&mut line, "level", &record.level().as_str().to_ascii_lowercase());
+    write_logfmt_pair(&mut line, "target", target);
+
+    if let Some(thread) = thread {
+        write_logfmt_pair(&mut line, "thread", thread);
+    }
+
+    write_logfmt_pair(&mut line, "msg", &record.args().to_string());
+    line
+}
+
+/// Write a `key=value` pair, quoting the value if it is empty or contains spaces, quotes, `=` or control characters.
+fn write_logfmt_pair(line: &mut String, key: &str, value: &str) {
+    if !line.is_empty() {
+        line.push(' ');
+    }
+
+    line.push_str(key);
+    line.push('=');
+
+    let needs_quotes = value.is_empty()
+        || value
+            .chars()
+            .any(|c| c == ' ' || c == '"' || c == '=' || c == '\\' || c.is_control());
+
+    if needs_quotes {
+        write_json_string(line, value);
+    } else {
+        line.push_str(value);
+    }
+}
+
+#[derive(Default)]
+struct JsonObject {
+    buffer: String,
+}
+
+impl JsonObject {
+    fn key(&mut self, key: &str) {
+        self.buffer.push(if self.buffer.is_empty() { '{' } else { ',' });
+        write_json_string(&mut self.buffer, key);
+        self.buffer.push(':');
+    }
+
+    fn string(&mut self, key: &str, value: &str) {
+        self.key(key);
+        write_json_string(&mut self.buffer, value);
+    }
+
+    fn number(&mut self, key: &str, value: u32) {
+        self.key(key);
+        let _ = write!(self.buffer, "{}", value);
+    }
+
+    fn null(&mut self, key: &str) {
+        self.raw(key, "null");
+    }
+
+    /// Write a value that is already valid JSON.
+    fn raw(&mut self, key: &str, value: &str) {
+        self.key(key);
+        self.buffer.push_str(value);
+    }
+
+    fn finish(mut self) -> String {
+        self.buffer.push('}');
+        self.buffer
+    }
+}
+
+/// Write a quoted JSON string, escaping quotes, backslashes and control characters.
+fn write_json_string(buffer: &mut String, value: &str) {
+    buffer.push('"');
+
+    for c in value.chars() {
+        match c {
+            '"' => buffer.push_str("\\\""),
+            '\\' => buffer.push_str("\\\\"),
+            '\n' => buffer.push_str("\\n"),
+            '\r' => buffer.push_str("\\r"),
+            '\t' => buffer.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(buffer, "\\u{:04x}", c as u32);
+            }
+            c => buffer.push(c),
+        }
+    }
+
+    buffer.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn test_json() {
+        let record = Record::builder()
+            .args(format_args!("a \"quoted\" message\nwith a newline"))
+            .level(Level::Warn)
+            .target("my_crate")
+            .module_path(Some("my_crate::module"))
+            .file(Some("src/module.rs"))
+            .line(Some(42))
+            .build();
+
+        assert_eq!(
+            json(
+                Some(Timestamp::Text("2026-10-16T12:00:00.000Z")),
+                &record,
+                "my_crate",
+                Some("main")
+            ),
+            concat!(
+                r#"{"timestamp":"2026-10-16T12:00:00.000Z","level":"warn","target":"my_crate","thread":"main","#,
+                r#""module_path":"my_crate::module","file":"src/module.rs","line":42,"#,
+                r#""message":"a \"quoted\" message\nwith a newline"}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_json_number_timestamp() {
+        let record = Record::builder()
+            .args(format_args!("a message"))
+            .level(Level::Info)
+            .target("my_crate")
+            .build();
+
+        assert!(
+            json(Some(Timestamp::Number("1792152000123")), &record, "my_crate", None)
+                .starts_with(r#"{"timestamp":1792152000123,"level":"info","#)
+        );
+    }
+
+    #[test]
+    fn test_json_missing_fields() {
+        let record = Record::builder()
+            .args(format_args!("tab\there"))
+            .level(Level::Info)
+            .target("")
+            .build();
+
+        assert_eq!(
+            json(None, &record, "", None),
+            r#"{"level":"info","target":"","module_path":null,"file":null,"line":null,"message":"tab\there"}"#
+        );
+    }
+
+    #[test]
+    fn test_logfmt() {
+        let record = Record::builder()
+            .args(format_args!("user \"sam\" logged in with a=b"))
+            .level(Level::Info)
+            .target("my_crate")
+            .build();
+
+        assert_eq!(
+            logfmt(Some("2026-10-16T12:00:00.000Z"), &record, "my_crate", Some("main")),
+            r#"ts=2026-10-16T12:00:00.000Z level=info target=my_crate thread=main msg="user \"sam\" logged in with a=b""#
+        );
+    }
+
+    #[test]
+    fn test_logfmt_quoting() {
+        let mut line = String::new();
+        write_logfmt_pair(&mut line, "a", "plain");
+        write_logfmt_pair(&mut line, "b", "");
+        write_logfmt_pair(&mut line, "c", "x=y");
+        write_logfmt_pair(&mut line, "d", "two\nlines");
+        assert_eq!(line, r#"a=plain b="" c="x=y" d="two\nlines""#);
+    }
+
+    #[test]
+    fn test_custom() {
+        let record = Record::builder()
+            .args(format_args!("a message"))
+            .level(Level::Info)
+            .target("my_crate")
+            .build();
+        let context = Context {
+            timestamp: Some("12:00"),
+            level: "INFO ",
+            target: "my_crate",
+            thread: None,
+        };
+
+        let line = custom(
+            &|w: &mut dyn io::Write, ctx: &Context, record: &Record| {
+                write!(w, "{} {} {}", ctx.target(), ctx.level().trim_end(), record.args())
+            },
+            &context,
+            &record,
+        );
+        assert_eq!(line.as_deref(), Some("my_crate INFO a message"));
+
+        let line = custom(
+            &|_: &mut dyn io::Write, _: &Context, _: &Record| Err(io::ErrorKind::Other.into()),
+            &context,
+            &record,
+        );
+        assert_eq!(line, None);
+    }
+
+    #[test]
+    fn test_json_control_characters() {
+        let mut buffer = String::new();
+        write_json_string(&mut buffer, "\u{1b}[31m\\");
+        assert_eq!(buffer, r#""\u001b[31m\\""#);
+    }
+}
@@ -0,0 +1,599 @@
+//! Log levels, shared between a [`SimpleLogger`](crate::SimpleLogger) and its [`Handle`]s.
+
+use crate::Directives;
+use log::LevelFilter;
+#[cfg(feature = "regex")]
+use log::Record;
+#[cfg(feature = "regex")]
+use regex::Regex;
+use std::{
+    collections::HashMap,
+    sync::{Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard},
+};
+#[cfg(all(unix, feature = "signals"))]
+use std::{fs, io, path::PathBuf, thread};
+
+/// The default log level and the levels for specific modules.
+#[derive(Debug)]
+pub(crate) struct Levels {
+    /// The default logging level
+    pub(crate) default_level: LevelFilter,
+
+    /// The specific logging level for each module
+    ///
+    /// This is used to override the default value for some specific modules.
+    pub(crate) module_levels: ModuleLevels,
+
+    /// Match module names as plain string prefixes of targets, so that `serde` also matches `serde_json`.
+    pub(crate) prefix_matching: bool,
+
+    /// Only records with a message matching this are logged.
+    ///
+    /// This field is only available if the `regex` feature is enabled.
+    #[cfg(feature = "regex")]
+    pub(crate) message_filter: Option<Result<Regex, regex::Error>>,
+
+    /// The level already resolved for each target, cleared by [`write`] whenever the levels might change.
+    cache: RwLock<HashMap<String, LevelFilter>>,
+}
+
+/// The most targets [`Levels::cached_level`] remembers, so that programs with dynamically created targets don't grow
+/// the cache forever.
+const CACHE_CAPACITY: usize = 1024;
+
+impl Levels {
+    pub(crate) fn new(default_level: LevelFilter) -> Levels {
+        Levels {
+            default_level,
+            module_levels: ModuleLevels::default(),
+            prefix_matching: false,
+            #[cfg(feature = "regex")]
+            message_filter: None,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Set the level for a module, replacing any existing level for the same module.
+    pub(crate) fn set_module_level(&mut self, target: &str, level: LevelFilter) {
+        self.module_levels.insert(target, level);
+    }
+
+    /// Replace the levels for all modules.
+    pub(crate) fn set_module_levels<I: IntoIterator<Item = (String, LevelFilter)>>(&mut self, module_levels: I) {
+        self.module_levels = ModuleLevels::default();
+
+        for (target, level) in module_levels {
+            self.module_levels.insert(&target, level);
+        }
+    }
+
+    pub(crate) fn remove_module_level(&mut self, target: &str) {
+        self.module_levels.remove(target);
+    }
+
+    /// The level for a target, using the most specific module level that matches.
+    pub(crate) fn level(&self, target: &str) -> LevelFilter {
+        self.module_levels
+            .lookup(target, self.prefix_matching)
+            .unwrap_or(self.default_level)
+    }
+
+    /// The level for a target, remembering it so that later calls for the same target only need a hash lookup.
+    ///
+    /// Once the cache is full, targets that aren't in it are looked up without taking the write lock.
+    pub(crate) fn cached_level(&self, target: &str) -> LevelFilter {
+        let full = {
+            let cache = self.cache.read().unwrap_or_else(PoisonError::into_inner);
+
+            if let Some(level) = cache.get(target) {
+                return *level;
+            }
+
+            cache.len() >= CACHE_CAPACITY
+        };
+
+        let level = self.level(target);
+
+        if !full {
+            let mut cache = self.cache.write().unwrap_or_else(PoisonError::into_inner);
+
+            // Other threads may have filled the cache since it was checked.
+            if cache.len() < CACHE_CAPACITY {
+                cache.insert(target.to_string(), level);
+            }
+        }
+
+        level
+    }
+
+    /// Check if a record's message matches the message filter, if there is a valid one.
+    #[cfg(feature = "regex")]
+    pub(crate) fn matches_message(&self, record: &Record) -> bool {
+        match &self.message_filter {
+            Some(Ok(filter)) => match record.args().as_str() {
+                Some(message) => filter.is_match(message),
+                None => filter.is_match(&record.args().to_string()),
+            },
+            _ => true,
+        }
+    }
+
+    /// The most verbose level any module can log at.
+    pub(crate) fn max_level(&self) -> LevelFilter {
+        self.module_levels
+            .max_level()
+            .map(|lvl| lvl.max(self.default_level))
+            .unwrap_or(self.default_level)
+    }
+}
+
+/// The levels for specific modules, stored as a tree of module path segments.
+///
+/// Looking up a target walks down the tree one segment at a time, so the most specific module that matches is found
+/// in time proportional to the depth of the target's path rather than the number of modules.
+///
+/// Other per-module settings, like sampling rates, use the same tree with a different value type.
+#[derive(Debug)]
+pub(crate) struct ModuleLevels<T = LevelFilter> {
+    root: Node<T>,
+    len: usize,
+}
+
+#[derive(Debug)]
+struct Node<T> {
+    level: Option<T>,
+    children: HashMap<String, Node<T>>,
+}
+
+impl<T> Default for ModuleLevels<T> {
+    fn default() -> Self {
+        ModuleLevels {
+            root: Node::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<T> Default for Node<T> {
+    fn default() -> Self {
+        Node {
+            level: None,
+            children: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Copy> ModuleLevels<T> {
+    /// The number of modules with a level.
+    #[cfg(test)]
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn insert(&mut self, name: &str, level: T) {
+        let node = segments(name).fold(&mut self.root, |node, segment| {
+            node.children.entry(segment.to_string()).or_default()
+        });
+
+        if node.level.replace(level).is_none() {
+            self.len += 1;
+        }
+    }
+
+    pub(crate) fn remove(&mut self, name: &str) {
+        if self.root.remove(&segments(name).collect::<Vec<_>>()).is_some() {
+            self.len -= 1;
+        }
+    }
+
+    /// The level of the most specific module that matches a target.
+    ///
+    /// With `prefix_matching`, a module also matches targets whose path merely starts with the module's name, e.g.
+    /// `serde` matches `serde_json` and `my_crate::d` matches `my_crate::db`.
+    pub(crate) fn lookup(&self, target: &str, prefix_matching: bool) -> Option<T> {
+        let mut node = &self.root;
+        let mut level = node.level;
+
+        for segment in segments(target) {
+            if prefix_matching {
+                let partial = node
+                    .children
+                    .iter()
+                    .filter(|(name, child)| child.level.is_some() && name.len() < segment.len())
+                    .filter(|(name, _child)| segment.starts_with(name.as_str()))
+                    .max_by_key(|(name, _child)| name.len());
+
+                if let Some((_name, child)) = partial {
+                    level = child.level;
+                }
+            }
+
+            match node.children.get(segment) {
+                Some(child) => node = child,
+                None => break,
+            }
+
+            level = node.level.or(level);
+        }
+
+        level
+    }
+}
+
+impl ModuleLevels<LevelFilter> {
+    fn max_level(&self) -> Option<LevelFilter> {
+        self.root.max_level()
+    }
+}
+
+impl<T> Node<T> {
+    /// Remove the level at a path, returning it if there was one.
+    fn remove(&mut self, path: &[&str]) -> Option<T> {
+        let (segment, rest) = match path.split_first() {
+            Some(split) => split,
+            None => return self.level.take(),
+        };
+
+        let child = self.children.get_mut(*segment)?;
+        let level = child.remove(rest);
+
+        if child.level.is_none() && child.children.is_empty() {
+            self.children.remove(*segment);
+        }
+
+        level
+    }
+}
+
+impl Node<LevelFilter> {
+    fn max_level(&self) -> Option<LevelFilter> {
+        self.children
+            .values()
+            .filter_map(Node::max_level)
+            .chain(self.level)
+            .max()
+    }
+}
+
+/// Split a module path into segments.
+///
+/// The empty module name has no segments, so it is stored at the root of the tree and matches every target.
+fn segments(path: &str) -> impl Iterator<Item = &str> {
+    path.split("::").filter(move |_| !path.is_empty())
+}
+
+/// Levels that can be shared with a [`Handle`].
+pub(crate) type SharedLevels = Arc<RwLock<Levels>>;
+
+pub(crate) fn read(levels: &SharedLevels) -> RwLockReadGuard<'_, Levels> {
+    levels.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Lock the levels for changing them, clearing the cache of resolved levels.
+pub(crate) fn write(levels: &SharedLevels) -> RwLockWriteGuard<'_, Levels> {
+    let mut levels = levels.write().unwrap_or_else(PoisonError::into_inner);
+    levels.cache.get_mut().unwrap_or_else(PoisonError::into_inner).clear();
+    levels
+}
+
+/// Changes the log levels of an installed [`SimpleLogger`](crate::SimpleLogger).
+///
+/// A handle is returned by
+/// [`SimpleLogger::init_with_handle`](crate::SimpleLogger::init_with_handle).
+/// Each change also updates [`log::max_level`] so that newly enabled messages
+/// aren't filtered out by the `log` crate.
+///
+/// ```no_run
+/// use log::LevelFilter;
+/// use simple_logger::SimpleLogger;
+///
+/// let handle = SimpleLogger::new().with_level(LevelFilter::Info).init_with_handle().unwrap();
+///
+/// // Later, while investigating a problem:
+/// handle.set_module_level("my_crate::db", LevelFilter::Trace);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Handle {
+    pub(crate) levels: SharedLevels,
+}
+
+impl Handle {
+    /// Change the default log level.
+    pub fn set_level(&self, level: LevelFilter) {
+        self.update(|levels| levels.default_level = level);
+    }
+
+    /// Change the log level for a module and its sub-modules.
+    ///
+    /// See [`SimpleLogger::with_module_level`](crate::SimpleLogger::with_module_level).
+    pub fn set_module_level(&self, target: &str, level: LevelFilter) {
+        self.update(|levels| levels.set_module_level(target, level));
+    }
+
+    /// Remove the log level for a module, so that it uses the default level (or the level of a parent module).
+    pub fn remove_module_level(&self, target: &str) {
+        self.update(|levels| levels.remove_module_level(target));
+    }
+
+    /// Replace the log levels with `RUST_LOG`-style directives.
+    ///
+    /// All module levels are replaced by the ones in `spec`, and the default
+    /// level is changed if `spec` contains one. See [`SimpleLogger::env`](crate::SimpleLogger::env)
+    /// for the syntax.
+    #[cfg(not(feature = "regex"))]
+    pub fn set_directives(&self, spec: &str) {
+        self.apply(Directives::parse(spec));
+    }
+
+    /// Replace the log levels with `RUST_LOG`-style directives.
+    ///
+    /// All module levels are replaced by the ones in `spec`, and the default
+    /// level is changed if `spec` contains one. The message filter is also
+    /// replaced by the one in `spec`, or removed if there isn't one. See [`SimpleLogger::env`](crate::SimpleLogger::env)
+    /// for the syntax.
+    ///
+    /// If the message filter isn't a valid regular expression, the error is
+    /// returned and the levels are left unchanged.
+    #[cfg(feature = "regex")]
+    pub fn set_directives(&self, spec: &str) -> Result<(), regex::Error> {
+        let directives = Directives::parse(spec);
+        let message_filter = directives.message_filter().transpose()?;
+        self.apply(directives, message_filter);
+        Ok(())
+    }
+
+    fn apply(&self, directives: Directives, #[cfg(feature = "regex")] message_filter: Option<Regex>) {
+        self.update(|levels| {
+            if let Some(level) = directives.level {
+                levels.default_level = level;
+            }
+
+            #[cfg(feature = "regex")]
+            {
+                levels.message_filter = message_filter.map(Ok);
+            }
+
+            levels.set_module_levels(directives.module_levels);
+        });
+    }
+
+    /// Reload the log levels from a file whenever the process receives `SIGHUP`.
+    ///
+    /// The file contains `RUST_LOG`-style directives, which are applied in the
+    /// same way as [`set_directives`](#method.set_directives). Directives can
+    /// be split over multiple lines as well as separated by commas, and blank
+    /// lines and lines starting with `#` are ignored. A `/` message filter
+    /// runs to the end of its line, so it must be on the last line.
+    ///
+    /// This starts a background thread that waits for the signal. If the file
+    /// can't be read, has a message filter before the last line or has a
+    /// message filter that isn't a valid regular expression, a warning is
+    /// logged and the levels are left unchanged.
+    ///
+    /// This method is only available on Unix if the `signals` feature is enabled.
+    ///
+    /// ```no_run
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// let handle = SimpleLogger::new().env().init_with_handle().unwrap();
+    /// handle.reload_on_sighup("/etc/my_daemon/log_levels").unwrap();
+    /// ```
+    #[cfg(all(unix, feature = "signals"))]
+    pub fn reload_on_sighup<P: Into<PathBuf>>(&self, path: P) -> io::Result<()> {
+        use signal_hook::{consts::SIGHUP, iterator::Signals};
+
+        let path = path.into();
+        let handle = self.clone();
+        let mut signals = Signals::new([SIGHUP])?;
+
+        thread::Builder::new()
+            .name("simple_logger-sighup".to_string())
+            .spawn(move || {
+                for _ in signals.forever() {
+                    match fs::read_to_string(&path).map(|contents| Directives::parse_lines(&contents)) {
+                        #[cfg(feature = "regex")]
+                        Ok(Ok(directives)) => match directives.message_filter().transpose() {
+                            Ok(message_filter) => handle.apply(directives, message_filter),
+                            Err(err) => log::warn!(
+                                "Could not reload log levels from {}: invalid message filter: {}",
+                                path.display(),
+                                err
+                            ),
+                        },
+                        #[cfg(not(feature = "regex"))]
+                        Ok(Ok(directives)) => handle.apply(directives),
+                        Ok(Err(line)) => log::warn!(
+                            "Could not reload log levels from {}: a message filter on line {} must be on the last line",
+                            path.display(),
+                            line
+                        ),
+                        Err(err) => log::warn!("Could not reload log levels from {}: {}", path.display(), err),
+                    }
+                }
+            })?;
+
+        Ok(())
+    }
+
+    /// The most verbose level any module can currently log at.
+    pub fn max_level(&self) -> LevelFilter {
+        read(&self.levels).max_level()
+    }
+
+    fn update<F: FnOnce(&mut Levels)>(&self, f: F) {
+        let mut levels = write(&self.levels);
+        f(&mut levels);
+        log::set_max_level(levels.max_level());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Handles change the global max level, so tests that use them can't run at the same time.
+    static MAX_LEVEL: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_level_path_boundaries() {
+        let mut levels = Levels::new(LevelFilter::Off);
+        levels.set_module_level("serde", LevelFilter::Error);
+        levels.set_module_level("my_crate", LevelFilter::Info);
+
+        assert_eq!(levels.level("serde"), LevelFilter::Error);
+        assert_eq!(levels.level("serde::de"), LevelFilter::Error);
+        assert_eq!(levels.level("serde_json"), LevelFilter::Off);
+        assert_eq!(levels.level("my_crate::db"), LevelFilter::Info);
+        assert_eq!(levels.level("my_crate_extras"), LevelFilter::Off);
+        assert_eq!(levels.level("my_crate_extras::db"), LevelFilter::Off);
+        assert_eq!(levels.level("my_cr"), LevelFilter::Off);
+    }
+
+    #[test]
+    fn test_level_most_specific() {
+        let mut levels = Levels::new(LevelFilter::Warn);
+        levels.set_module_level("a", LevelFilter::Off);
+        levels.set_module_level("a::b::c", LevelFilter::Off);
+        levels.set_module_level("a::b", LevelFilter::Info);
+        levels.set_module_level("", LevelFilter::Error);
+
+        assert_eq!(levels.level("a"), LevelFilter::Off);
+        assert_eq!(levels.level("a::x"), LevelFilter::Off);
+        assert_eq!(levels.level("a::b"), LevelFilter::Info);
+        assert_eq!(levels.level("a::b::x"), LevelFilter::Info);
+        assert_eq!(levels.level("a::b::c::d"), LevelFilter::Off);
+        assert_eq!(levels.level("other"), LevelFilter::Error);
+        assert_eq!(levels.max_level(), LevelFilter::Info);
+
+        levels.remove_module_level("a::b");
+        levels.remove_module_level("");
+        assert_eq!(levels.module_levels.len(), 2);
+        assert_eq!(levels.level("a::b::x"), LevelFilter::Off);
+        assert_eq!(levels.level("a::b::c"), LevelFilter::Off);
+        assert_eq!(levels.level("other"), LevelFilter::Warn);
+    }
+
+    #[test]
+    fn test_level_prefix_matching() {
+        let mut levels = Levels::new(LevelFilter::Off);
+        levels.prefix_matching = true;
+        levels.set_module_level("serde", LevelFilter::Error);
+
+        assert_eq!(levels.level("serde::de"), LevelFilter::Error);
+        assert_eq!(levels.level("serde_json"), LevelFilter::Error);
+        assert_eq!(levels.level("ser"), LevelFilter::Off);
+
+        levels.set_module_level("serde_json::de", LevelFilter::Trace);
+        assert_eq!(levels.level("serde_json::de"), LevelFilter::Trace);
+        assert_eq!(levels.level("serde_json::der"), LevelFilter::Trace);
+        assert_eq!(levels.level("serde_json::ser"), LevelFilter::Error);
+    }
+
+    #[test]
+    fn test_cached_level() {
+        let levels: SharedLevels = Arc::new(RwLock::new(Levels::new(LevelFilter::Info)));
+        assert_eq!(read(&levels).cached_level("my_crate::db"), LevelFilter::Info);
+        assert_eq!(read(&levels).cache.read().unwrap().len(), 1);
+
+        write(&levels).set_module_level("my_crate", LevelFilter::Trace);
+        assert_eq!(read(&levels).cache.read().unwrap().len(), 0);
+        assert_eq!(read(&levels).cached_level("my_crate::db"), LevelFilter::Trace);
+
+        for n in 0..CACHE_CAPACITY * 2 {
+            read(&levels).cached_level(&format!("target_{}", n));
+        }
+        assert_eq!(read(&levels).cache.read().unwrap().len(), CACHE_CAPACITY);
+    }
+
+    #[test]
+    fn test_handle() {
+        let _guard = MAX_LEVEL.lock().unwrap_or_else(PoisonError::into_inner);
+        let handle = Handle {
+            levels: Arc::new(RwLock::new(Levels::new(LevelFilter::Info))),
+        };
+
+        handle.set_module_level("my_crate", LevelFilter::Trace);
+        assert_eq!(handle.max_level(), LevelFilter::Trace);
+        assert_eq!(log::max_level(), LevelFilter::Trace);
+        assert_eq!(read(&handle.levels).level("my_crate::db"), LevelFilter::Trace);
+
+        assert_eq!(read(&handle.levels).cached_level("my_crate::db"), LevelFilter::Trace);
+
+        handle.remove_module_level("my_crate");
+        handle.set_level(LevelFilter::Warn);
+        assert_eq!(read(&handle.levels).cached_level("my_crate::db"), LevelFilter::Warn);
+        assert_eq!(handle.max_level(), LevelFilter::Warn);
+        assert_eq!(log::max_level(), LevelFilter::Warn);
+        assert_eq!(read(&handle.levels).level("my_crate::db"), LevelFilter::Warn);
+    }
+
+    #[test]
+    #[cfg_attr(feature = "regex", allow(unused_must_use))]
+    fn test_handle_set_directives() {
+        let _guard = MAX_LEVEL.lock().unwrap_or_else(PoisonError::into_inner);
+        let handle = Handle {
+            levels: Arc::new(RwLock::new(Levels::new(LevelFilter::Info))),
+        };
+        handle.set_module_level("old", LevelFilter::Trace);
+
+        handle.set_directives("my_crate=debug");
+        assert_eq!(read(&handle.levels).default_level, LevelFilter::Info);
+        assert_eq!(read(&handle.levels).module_levels.len(), 1);
+        assert_eq!(read(&handle.levels).level("my_crate"), LevelFilter::Debug);
+        assert_eq!(read(&handle.levels).level("old"), LevelFilter::Info);
+
+        handle.set_directives("off,my_crate=trace");
+        assert_eq!(read(&handle.levels).default_level, LevelFilter::Off);
+        assert_eq!(read(&handle.levels).level("my_crate"), LevelFilter::Trace);
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_handle_set_directives_message_filter() {
+        let _guard = MAX_LEVEL.lock().unwrap_or_else(PoisonError::into_inner);
+        let handle = Handle {
+            levels: Arc::new(RwLock::new(Levels::new(LevelFilter::Info))),
+        };
+        let matches = |message: &str| {
+            read(&handle.levels).matches_message(&Record::builder().args(format_args!("{}", message)).build())
+        };
+
+        handle.set_directives("info/req-\\d+").unwrap();
+        assert!(matches("handling req-42"));
+        assert!(!matches("handling req-x"));
+
+        assert!(handle.set_directives("debug/req-(").is_err());
+        assert_eq!(read(&handle.levels).default_level, LevelFilter::Info);
+        assert!(!matches("handling req-x"));
+
+        handle.set_directives("info").unwrap();
+        assert!(matches("handling req-x"));
+    }
+
+    #[test]
+    #[cfg(all(unix, feature = "signals"))]
+    fn test_reload_on_sighup() {
+        use crate::file::TempFile;
+
+        let _guard = MAX_LEVEL.lock().unwrap_or_else(PoisonError::into_inner);
+        let file = TempFile::new();
+        fs::write(&file, "# levels\nwarn\n\nmy_crate=trace\n").unwrap();
+
+        let handle = Handle {
+            levels: Arc::new(RwLock::new(Levels::new(LevelFilter::Info))),
+        };
+        handle.reload_on_sighup(file.path()).unwrap();
+        signal_hook::low_level::raise(signal_hook::consts::SIGHUP).unwrap();
+
+        for _ in 0..100 {
+            if read(&handle.levels).default_level == LevelFilter::Warn {
+                break;
+            }
+            thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        assert_eq!(read(&handle.levels).default_level, LevelFilter::Warn);
+        assert_eq!(read(&handle.levels).level("my_crate"), LevelFilter::Trace);
+    }
+}
@@ -2,6 +2,16 @@
 //!
 //! Optional features include timestamps, colored output and logging to stderr.
 //!
+//! Messages are written to stdout by default, or to stderr if the `stderr`
+//! feature is enabled. The output stream can also be chosen at runtime with
+//! [`SimpleLogger::with_output`]:
+//!
+//! ```rust
+//! use simple_logger::{Output, SimpleLogger};
+//!
+//! SimpleLogger::new().with_output(Output::Stderr).init().unwrap();
+//! ```
+//!
 //! ```rust
 //! simple_logger::SimpleLogger::new().env().init().unwrap();
 //!
@@ -30,12 +40,56 @@
 
 #![cfg_attr(feature = "nightly", feature(thread_id_value))]
 
+mod dedup;
+mod file;
+mod format;
+mod levels;
+mod rate_limit;
+mod sample;
+mod template;
+#[cfg(feature = "timezones")]
+mod tz;
+
 #[cfg(feature = "colors")]
 use colored::*;
+use dedup::{Deduplicator, Repeated};
+use file::FileSink;
+pub use file::Rotation;
+pub use format::Context;
+use format::FormatFn;
+pub use levels::Handle;
+use levels::{Levels, SharedLevels};
 use log::{Level, LevelFilter, Log, Metadata, Record, SetLoggerError};
-use std::{collections::HashMap, str::FromStr};
+use rate_limit::{RateLimiter, Suppressed};
+use sample::Sampler;
+#[cfg(feature = "colors")]
+use std::ffi::OsString;
+#[cfg(feature = "timestamps")]
+use std::sync::{Mutex, OnceLock, PoisonError};
+use std::{
+    collections::HashMap,
+    fmt, io,
+    io::Write,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::{Duration, Instant},
+};
+use template::Template;
+pub use template::TemplateError;
 #[cfg(feature = "timestamps")]
-use time::{format_description::FormatItem, OffsetDateTime, UtcOffset};
+use time::{
+    error::{IndeterminateOffset, InvalidFormatDescription},
+    format_description::{self, FormatItem, OwnedFormatItem},
+    OffsetDateTime, UtcOffset,
+};
+#[cfg(feature = "timezones")]
+use tz::TimeZone;
+#[cfg(feature = "timezones")]
+pub use tz::TimezoneError;
 
 #[cfg(feature = "timestamps")]
 const TIMESTAMP_FORMAT_OFFSET: &[FormatItem] = time::macros::format_description!(
@@ -53,6 +107,181 @@ enum Timestamps {
     Local,
     Utc,
     UtcOffset(UtcOffset),
+    /// A named timezone, which may have failed to load.
+    #[cfg(feature = "timezones")]
+    Timezone(Result<TimeZone, TimezoneError>),
+    /// The time since the logger was initialised.
+    Elapsed,
+    /// The time since the previous message.
+    Delta,
+    /// The time since the Unix epoch, as a number.
+    Epoch(EpochPrecision),
+}
+
+/// The unit used for Unix epoch timestamps.
+///
+/// See [`SimpleLogger::with_epoch_timestamps`].
+///
+/// This enum is only available if the `timestamps` feature is enabled.
+#[cfg(feature = "timestamps")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EpochPrecision {
+    /// Whole seconds, e.g. `1792152000`.
+    Seconds,
+    /// Milliseconds, e.g. `1792152000123`.
+    Millis,
+    /// Microseconds, e.g. `1792152000123456`.
+    Micros,
+    /// Nanoseconds, e.g. `1792152000123456789`.
+    Nanos,
+}
+
+#[cfg(feature = "timestamps")]
+impl EpochPrecision {
+    /// Format the time since the Unix epoch in this unit.
+    fn format(self, now: OffsetDateTime) -> String {
+        let nanos = now.unix_timestamp_nanos();
+        let unit = match self {
+            EpochPrecision::Seconds => 1_000_000_000,
+            EpochPrecision::Millis => 1_000_000,
+            EpochPrecision::Micros => 1_000,
+            EpochPrecision::Nanos => 1,
+        };
+
+        nanos.div_euclid(unit).to_string()
+    }
+}
+
+/// A function that is told about errors writing messages.
+///
+/// See [`SimpleLogger::with_error_handler`].
+type ErrorFn = dyn Fn(&io::Error) + Send + Sync;
+
+/// The stream that log messages are written to.
+///
+/// See [`SimpleLogger::with_output`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Output {
+    /// Write all messages to stdout.
+    Stdout,
+    /// Write all messages to stderr.
+    Stderr,
+    /// Write errors and warnings to stderr, and all other messages to stdout.
+    Split,
+}
+
+impl Output {
+    /// The stream a message at the given level is written to.
+    ///
+    /// This always returns [`Output::Stdout`] or [`Output::Stderr`].
+    fn stream(self, level: Level) -> Output {
+        match self {
+            Output::Split if level <= Level::Warn => Output::Stderr,
+            Output::Split => Output::Stdout,
+            output => output,
+        }
+    }
+}
+
+impl Default for Output {
+    /// Uses stderr if the `stderr` feature is enabled, and stdout otherwise.
+    fn default() -> Self {
+        if cfg!(feature = "stderr") {
+            Output::Stderr
+        } else {
+            Output::Stdout
+        }
+    }
+}
+
+/// An error returned by [`SimpleLogger::init`].
+///
+/// More variants may be added by optional features, so matching on this enum
+/// needs a wildcard arm.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum InitError {
+    /// A logger has already been set.
+    SetLogger(SetLoggerError),
+    /// The file set by [`SimpleLogger::with_output_file`] could not be opened.
+    OpenFile { path: PathBuf, source: io::Error },
+    /// The template set by [`SimpleLogger::with_format_template`] is invalid.
+    Template(TemplateError),
+    /// A rate set by [`SimpleLogger::with_sampling`] or
+    /// [`SimpleLogger::with_module_sampling`] isn't between 0 and 1.
+    SamplingRate(f64),
+    /// The regular expression set by [`SimpleLogger::with_message_filter`], or
+    /// given in `RUST_LOG` to [`SimpleLogger::env`], is invalid.
+    ///
+    /// This variant is only available if the `regex` feature is enabled.
+    #[cfg(feature = "regex")]
+    MessageFilter(regex::Error),
+    /// The timezone set by [`SimpleLogger::with_timezone`] could not be loaded.
+    ///
+    /// This variant is only available if the `timezones` feature is enabled.
+    #[cfg(feature = "timezones")]
+    Timezone(TimezoneError),
+    /// The format set by [`SimpleLogger::with_timestamp_format_str`] is invalid.
+    ///
+    /// This variant is only available if the `timestamps` feature is enabled.
+    #[cfg(feature = "timestamps")]
+    TimestampFormat(InvalidFormatDescription),
+}
+
+impl fmt::Display for InitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InitError::SetLogger(err) => err.fmt(f),
+            InitError::OpenFile { path, source } => {
+                write!(f, "could not open log file {}: {}", path.display(), source)
+            }
+            InitError::Template(err) => err.fmt(f),
+            InitError::SamplingRate(rate) => write!(f, "sampling rates must be between 0 and 1, got {}", rate),
+            #[cfg(feature = "regex")]
+            InitError::MessageFilter(err) => write!(f, "invalid log message filter: {}", err),
+            #[cfg(feature = "timezones")]
+            InitError::Timezone(err) => err.fmt(f),
+            #[cfg(feature = "timestamps")]
+            InitError::TimestampFormat(err) => write!(f, "invalid timestamp format: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for InitError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            InitError::SetLogger(err) => Some(err),
+            InitError::OpenFile { source, .. } => Some(source),
+            InitError::Template(err) => Some(err),
+            InitError::SamplingRate(_) => None,
+            #[cfg(feature = "regex")]
+            InitError::MessageFilter(err) => Some(err),
+            #[cfg(feature = "timezones")]
+            InitError::Timezone(err) => Some(err),
+            #[cfg(feature = "timestamps")]
+            InitError::TimestampFormat(err) => Some(err),
+        }
+    }
+}
+
+impl From<SetLoggerError> for InitError {
+    fn from(err: SetLoggerError) -> Self {
+        InitError::SetLogger(err)
+    }
+}
+
+/// The layout of each line.
+enum Format {
+    /// `{timestamp} {level} [{target}{thread}] {message}`
+    Text,
+    /// One JSON object per line.
+    Json,
+    /// One set of logfmt key/value pairs per line.
+    Logfmt,
+    /// A user-supplied function.
+    Custom(Box<FormatFn>),
+    /// A template string, which may have failed to parse.
+    Template(Result<Template, TemplateError>),
 }
 
 /// Implements [`Log`] and a set of simple builder methods for configuration.
@@ -60,16 +289,10 @@ enum Timestamps {
 /// Use the various "builder" methods on this struct to configure the logger,
 /// then call [`init`] to configure the [`log`] crate.
 pub struct SimpleLogger {
-    /// The default logging level
-    default_level: LevelFilter,
-
-    /// The specific logging level for each module
-    ///
-    /// This is used to override the default value for some specific modules.
+    /// The default logging level and the specific logging level for each module
     ///
-    /// This must be sorted from most-specific to least-specific, so that [`enabled`](#method.enabled) can scan the
-    /// vector for the first match to give us the desired log level for a module.
-    module_levels: Vec<(String, LevelFilter)>,
+    /// These are shared with any [`Handle`] so they can be changed after [`init_with_handle`](#method.init_with_handle).
+    levels: SharedLevels,
 
     /// Whether to include thread names (and IDs) or not
     ///
@@ -83,13 +306,63 @@ pub struct SimpleLogger {
     #[cfg(feature = "timestamps")]
     timestamps: Timestamps,
     #[cfg(feature = "timestamps")]
-    timestamps_format: Option<&'static [FormatItem<'static>]>,
+    timestamps_format: Option<Result<OwnedFormatItem, InvalidFormatDescription>>,
+
+    /// The local UTC offset, once it has been looked up.
+    #[cfg(feature = "timestamps")]
+    local_offset: OnceLock<UtcOffset>,
+
+    /// The offset used if the local UTC offset can't be determined.
+    #[cfg(feature = "timestamps")]
+    local_offset_fallback: UtcOffset,
+
+    /// When the logger was initialised, for elapsed timestamps.
+    #[cfg(feature = "timestamps")]
+    start: Instant,
+
+    /// When the previous message was logged, for delta timestamps.
+    #[cfg(feature = "timestamps")]
+    previous: Mutex<Instant>,
 
     /// Whether to use color output or not.
     ///
     /// This field is only available if the `color` feature is enabled.
     #[cfg(feature = "colors")]
     colors: bool,
+
+    /// Whether messages written to stdout and stderr can be colored, decided once by [`init`](#method.init) from
+    /// whether each stream is a terminal and the `NO_COLOR`, `CLICOLOR` and `CLICOLOR_FORCE` environment variables.
+    #[cfg(feature = "colors")]
+    stdout_colors: bool,
+    #[cfg(feature = "colors")]
+    stderr_colors: bool,
+
+    /// The layout of each line.
+    format: Format,
+
+    /// The stream that messages are written to.
+    output: Output,
+
+    /// A file that messages are written to instead of [`output`](#structfield.output).
+    file: Option<FileSink>,
+
+    /// When to start a new file.
+    file_rotation: Rotation,
+
+    /// Keeps only a fraction of the messages at some levels.
+    sampler: Option<Sampler>,
+
+    /// Limits how many messages each call site can log.
+    rate_limit: Option<RateLimiter>,
+
+    /// Collapses consecutive identical messages.
+    dedup: Option<Deduplicator>,
+
+    /// Called with the first error from writing a message.
+    error_handler: Option<Box<ErrorFn>>,
+
+    /// Whether the error handler has been called.
+    error_reported: AtomicBool,
 }
 
 impl SimpleLogger {
@@ -106,8 +379,7 @@ impl SimpleLogger {
     #[must_use = "You must call init() to begin logging"]
     pub fn new() -> SimpleLogger {
         SimpleLogger {
-            default_level: LevelFilter::Trace,
-            module_levels: Vec::new(),
+            levels: Arc::new(RwLock::new(Levels::new(LevelFilter::Trace))),
 
             #[cfg(feature = "threads")]
             threads: false,
@@ -118,8 +390,36 @@ impl SimpleLogger {
             #[cfg(feature = "timestamps")]
             timestamps_format: None,
 
+            #[cfg(feature = "timestamps")]
+            local_offset: OnceLock::new(),
+
+            #[cfg(feature = "timestamps")]
+            local_offset_fallback: UtcOffset::UTC,
+
+            #[cfg(feature = "timestamps")]
+            start: Instant::now(),
+
+            #[cfg(feature = "timestamps")]
+            previous: Mutex::new(Instant::now()),
+
             #[cfg(feature = "colors")]
             colors: true,
+
+            #[cfg(feature = "colors")]
+            stdout_colors: false,
+
+            #[cfg(feature = "colors")]
+            stderr_colors: false,
+
+            format: Format::Text,
+            output: Output::default(),
+            file: None,
+            file_rotation: Rotation::Never,
+            sampler: None,
+            rate_limit: None,
+            dedup: None,
+            error_handler: None,
+            error_reported: AtomicBool::new(false),
         }
     }
 
@@ -145,23 +445,54 @@ impl SimpleLogger {
         SimpleLogger::new().with_level(log::LevelFilter::Error).env()
     }
 
-    /// Enables the user to choose log level by setting `RUST_LOG=<level>`
-    /// environment variable. This will use the default level set by
-    /// [`with_level`] if `RUST_LOG` is not set or can't be parsed as a
-    /// standard log level.
+    /// Enables the user to choose log levels by setting the `RUST_LOG`
+    /// environment variable.
+    ///
+    /// The variable is parsed as a comma-separated list of directives in the
+    /// same style as `env_logger`:
+    ///
+    /// - `<level>` sets the default level (e.g. `RUST_LOG=info`).
+    /// - `<target>=<level>` sets the level for a module and its sub-modules,
+    ///   as [`with_module_level`] does (e.g. `RUST_LOG=info,hyper=warn`).
+    /// - `<target>` enables all logging for a module and its sub-modules.
+    ///
+    /// The directives can be followed by `/<regex>` to only log messages that
+    /// match the regular expression, as [`with_message_filter`] does (e.g.
+    /// `RUST_LOG=info/req-1234`). This requires the `regex` feature, and is
+    /// ignored without it.
+    ///
+    /// Directives that can't be parsed are ignored, but an invalid regular
+    /// expression makes [`init`] return an error. This will use the default
+    /// level set by [`with_level`] if `RUST_LOG` is not set or doesn't
+    /// contain a default level.
     ///
     /// This must be called after [`with_level`]. If called before
-    /// [`with_level`], it will have no effect.
+    /// [`with_level`], the default level will have no effect.
     ///
+    /// [`init`]: #method.init
     /// [`with_level`]: #method.with_level
+    /// [`with_module_level`]: #method.with_module_level
+    /// [`with_message_filter`]: #method.with_message_filter
     #[must_use = "You must call init() to begin logging"]
-    pub fn env(mut self) -> SimpleLogger {
-        self.default_level = std::env::var("RUST_LOG")
-            .ok()
-            .as_deref()
-            .map(log::LevelFilter::from_str)
-            .and_then(Result::ok)
-            .unwrap_or(self.default_level);
+    pub fn env(self) -> SimpleLogger {
+        if let Ok(spec) = std::env::var("RUST_LOG") {
+            let directives = Directives::parse(&spec);
+
+            let mut levels = levels::write(&self.levels);
+
+            if let Some(level) = directives.level {
+                levels.default_level = level;
+            }
+
+            #[cfg(feature = "regex")]
+            if let Some(filter) = directives.message_filter() {
+                levels.message_filter = Some(filter);
+            }
+
+            for (target, level) in directives.module_levels {
+                levels.set_module_level(&target, level);
+            }
+        }
 
         self
     }
@@ -175,17 +506,20 @@ impl SimpleLogger {
     /// [`env`]: #method.env
     /// [`with_module_level`]: #method.with_module_level
     #[must_use = "You must call init() to begin logging"]
-    pub fn with_level(mut self, level: LevelFilter) -> SimpleLogger {
-        self.default_level = level;
+    pub fn with_level(self, level: LevelFilter) -> SimpleLogger {
+        levels::write(&self.levels).default_level = level;
         self
     }
 
     /// Override the log level for some specific modules.
     ///
     /// This sets the log level of a specific module and all its sub-modules.
+    /// A module only matches targets that are the module itself or are inside
+    /// it, so `my_crate` matches `my_crate::db` but not `my_crate_extras`
+    /// (see [`with_module_prefix_matching`] to change this).
     /// When both the level for a parent module as well as a child module are set,
     /// the more specific value is taken. If the log level for the same module is
-    /// specified twice, the resulting log level is implementation defined.
+    /// specified twice, the last value is used.
     ///
     /// # Examples
     ///
@@ -210,27 +544,63 @@ impl SimpleLogger {
     ///     .init()
     ///     .unwrap();
     /// ```
-    //
-    // This method *must* sort `module_levels` for the [`enabled`](#method.enabled) method to work correctly.
+    ///
+    /// [`with_module_prefix_matching`]: #method.with_module_prefix_matching
+    #[must_use = "You must call init() to begin logging"]
+    pub fn with_module_level(self, target: &str, level: LevelFilter) -> SimpleLogger {
+        levels::write(&self.levels).set_module_level(target, level);
+        self
+    }
+
+    /// Match module names as plain string prefixes of targets.
+    ///
+    /// By default, a module set by [`with_module_level`] only matches targets
+    /// that are the module itself or one of its sub-modules (separated by
+    /// `::`). With prefix matching enabled, a module matches any target that
+    /// starts with its name, so `serde` also matches `serde_json`. This was
+    /// the behaviour of earlier versions.
+    ///
+    /// [`with_module_level`]: #method.with_module_level
     #[must_use = "You must call init() to begin logging"]
-    pub fn with_module_level(mut self, target: &str, level: LevelFilter) -> SimpleLogger {
-        self.module_levels.push((target.to_string(), level));
-        self.module_levels
-            .sort_by_key(|(name, _level)| name.len().wrapping_neg());
+    pub fn with_module_prefix_matching(self, prefix_matching: bool) -> SimpleLogger {
+        levels::write(&self.levels).prefix_matching = prefix_matching;
         self
     }
 
     /// Override the log level for specific targets.
-    // This method *must* sort `module_levels` for the [`enabled`](#method.enabled) method to work correctly.
     #[must_use = "You must call init() to begin logging"]
     #[deprecated(
         since = "1.11.0",
         note = "Use [`with_module_level`](#method.with_module_level) instead. Will be removed in version 2.0.0."
     )]
-    pub fn with_target_levels(mut self, target_levels: HashMap<String, LevelFilter>) -> SimpleLogger {
-        self.module_levels = target_levels.into_iter().collect();
-        self.module_levels
-            .sort_by_key(|(name, _level)| name.len().wrapping_neg());
+    pub fn with_target_levels(self, target_levels: HashMap<String, LevelFilter>) -> SimpleLogger {
+        levels::write(&self.levels).set_module_levels(target_levels);
+        self
+    }
+
+    /// Only log messages that match a regular expression.
+    ///
+    /// The expression is matched against the message itself, not the rest of
+    /// the line, and can match anywhere in it. This is useful for following a
+    /// single request through noisy output:
+    ///
+    /// ```no_run
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// SimpleLogger::new().with_message_filter("req-1234").init().unwrap();
+    /// ```
+    ///
+    /// The expression is validated by [`init`], which returns an error if it
+    /// is invalid. If the logger is used without calling `init()`, an invalid
+    /// expression is ignored.
+    ///
+    /// This method is only available if the `regex` feature is enabled.
+    ///
+    /// [`init`]: #method.init
+    #[must_use = "You must call init() to begin logging"]
+    #[cfg(feature = "regex")]
+    pub fn with_message_filter(self, filter: &str) -> SimpleLogger {
+        levels::write(&self.levels).message_filter = Some(regex::Regex::new(filter));
         self
     }
 
@@ -283,7 +653,36 @@ impl SimpleLogger {
     #[must_use = "You must call init() to begin logging"]
     #[cfg(feature = "timestamps")]
     pub fn with_timestamp_format(mut self, format: &'static [FormatItem<'static>]) -> SimpleLogger {
-        self.timestamps_format = Some(format);
+        self.timestamps_format = Some(Ok(format.into()));
+        self
+    }
+
+    /// Control the format used for timestamps, using a format description
+    /// that is parsed at runtime.
+    ///
+    /// This does the same as [`with_timestamp_format`], but takes the format
+    /// as a string so that it can be read from configuration. The format uses
+    /// the same syntax as the `format_description!` macro. It is parsed
+    /// immediately, and errors are returned by [`init`]. If the logger is used
+    /// without calling `init()`, the default format is used if the format is
+    /// invalid.
+    ///
+    /// ```no_run
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// let format = std::env::var("LOG_TIMESTAMP_FORMAT").unwrap_or_else(|_| "[hour]:[minute]:[second]".into());
+    ///
+    /// SimpleLogger::new().with_timestamp_format_str(&format).init().unwrap();
+    /// ```
+    ///
+    /// This method is only available if the `timestamps` feature is enabled.
+    ///
+    /// [`with_timestamp_format`]: #method.with_timestamp_format
+    /// [`init`]: #method.init
+    #[must_use = "You must call init() to begin logging"]
+    #[cfg(feature = "timestamps")]
+    pub fn with_timestamp_format_str(mut self, format: &str) -> SimpleLogger {
+        self.timestamps_format = Some(format_description::parse_owned::<1>(format));
         self
     }
 
@@ -299,7 +698,22 @@ impl SimpleLogger {
 
     /// Display timestamps using the local timezone.
     ///
+    /// The local UTC offset is looked up once, by [`init`], and used for all
+    /// messages. It can only be looked up safely before the program starts
+    /// any threads, so call [`init`] at the start of `main()`. If the offset
+    /// can't be determined, a warning is logged and the offset set by
+    /// [`with_local_offset_fallback`] (UTC by default) is used instead.
+    ///
+    /// As the offset doesn't change after [`init`], timestamps from a
+    /// long-running program will be off by the difference once daylight
+    /// saving time starts or ends. Use [`with_timezone`] to follow the
+    /// timezone's daylight saving time rules instead.
+    ///
     /// This method is only available if the `timestamps` feature is enabled.
+    ///
+    /// [`init`]: #method.init
+    /// [`with_local_offset_fallback`]: #method.with_local_offset_fallback
+    /// [`with_timezone`]: #method.with_timezone
     #[must_use = "You must call init() to begin logging"]
     #[cfg(feature = "timestamps")]
     pub fn with_local_timestamps(mut self) -> SimpleLogger {
@@ -307,6 +721,31 @@ impl SimpleLogger {
         self
     }
 
+    /// Set the offset used by [`with_local_timestamps`] if the local UTC
+    /// offset can't be determined.
+    ///
+    /// Defaults to UTC.
+    ///
+    /// ```no_run
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// SimpleLogger::new()
+    ///     .with_local_timestamps()
+    ///     .with_local_offset_fallback(time::macros::offset!(+01:00))
+    ///     .init()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// This method is only available if the `timestamps` feature is enabled.
+    ///
+    /// [`with_local_timestamps`]: #method.with_local_timestamps
+    #[must_use = "You must call init() to begin logging"]
+    #[cfg(feature = "timestamps")]
+    pub fn with_local_offset_fallback(mut self, offset: UtcOffset) -> SimpleLogger {
+        self.local_offset_fallback = offset;
+        self
+    }
+
     /// Display timestamps using UTC.
     ///
     /// This method is only available if the `timestamps` feature is enabled.
@@ -327,94 +766,725 @@ impl SimpleLogger {
         self
     }
 
-    /// Control whether messages are colored or not.
+    /// Display the time since the logger was initialised, e.g. `+12.345s`,
+    /// instead of the date and time.
     ///
-    /// This method is only available if the `colored` feature is enabled.
+    /// The time is measured with a monotonic clock, so it isn't affected by
+    /// changes to the system clock. This is useful for benchmarks and for
+    /// tracing command line tools. [`with_timestamp_format`] has no effect.
+    ///
+    /// ```no_run
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// SimpleLogger::new().with_elapsed_timestamps().init().unwrap();
+    /// ```
+    ///
+    /// This method is only available if the `timestamps` feature is enabled.
+    ///
+    /// [`with_timestamp_format`]: #method.with_timestamp_format
     #[must_use = "You must call init() to begin logging"]
-    #[cfg(feature = "colors")]
-    pub fn with_colors(mut self, colors: bool) -> SimpleLogger {
-        self.colors = colors;
+    #[cfg(feature = "timestamps")]
+    pub fn with_elapsed_timestamps(mut self) -> SimpleLogger {
+        self.timestamps = Timestamps::Elapsed;
         self
     }
 
-    /// Configure the logger
-    pub fn max_level(&self) -> LevelFilter {
-        let max_level = self.module_levels.iter().map(|(_name, level)| level).copied().max();
-        max_level
-            .map(|lvl| lvl.max(self.default_level))
-            .unwrap_or(self.default_level)
+    /// Display the time since the previous message, e.g. `+0.012s`, instead
+    /// of the date and time.
+    ///
+    /// The first message shows the time since the logger was initialised. As
+    /// with [`with_elapsed_timestamps`], the time is measured with a
+    /// monotonic clock and [`with_timestamp_format`] has no effect.
+    ///
+    /// This method is only available if the `timestamps` feature is enabled.
+    ///
+    /// [`with_elapsed_timestamps`]: #method.with_elapsed_timestamps
+    /// [`with_timestamp_format`]: #method.with_timestamp_format
+    #[must_use = "You must call init() to begin logging"]
+    #[cfg(feature = "timestamps")]
+    pub fn with_delta_timestamps(mut self) -> SimpleLogger {
+        self.timestamps = Timestamps::Delta;
+        self
     }
 
-    /// 'Init' the actual logger and instantiate it,
-    /// this method MUST be called in order for the logger to be effective.
-    pub fn init(self) -> Result<(), SetLoggerError> {
-        #[cfg(all(windows, feature = "colored"))]
-        set_up_windows_color_terminal();
-
-        #[cfg(all(feature = "colored", feature = "stderr"))]
-        use_stderr_for_colors();
-
-        log::set_max_level(self.max_level());
-        log::set_boxed_logger(Box::new(self))
+    /// Display timestamps as the time since the Unix epoch, e.g.
+    /// `1792152000123` for [`EpochPrecision::Millis`].
+    ///
+    /// Timestamps are written as numbers in structured formats, e.g.
+    /// `{"timestamp":1792152000123,...}` with [`with_json`].
+    /// [`with_timestamp_format`] has no effect.
+    ///
+    /// ```no_run
+    /// use simple_logger::{EpochPrecision, SimpleLogger};
+    ///
+    /// SimpleLogger::new()
+    ///     .with_epoch_timestamps(EpochPrecision::Millis)
+    ///     .with_json()
+    ///     .init()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// This method is only available if the `timestamps` feature is enabled.
+    ///
+    /// [`with_json`]: #method.with_json
+    /// [`with_timestamp_format`]: #method.with_timestamp_format
+    #[must_use = "You must call init() to begin logging"]
+    #[cfg(feature = "timestamps")]
+    pub fn with_epoch_timestamps(mut self, precision: EpochPrecision) -> SimpleLogger {
+        self.timestamps = Timestamps::Epoch(precision);
+        self
     }
-}
 
-impl Default for SimpleLogger {
-    /// See [this](struct.SimpleLogger.html#method.new)
-    fn default() -> Self {
-        SimpleLogger::new()
+    /// Display timestamps using a named timezone from the IANA timezone
+    /// database, e.g. `Europe/London`.
+    ///
+    /// Unlike [`with_utc_offset`], the offset for each message follows the
+    /// timezone's daylight saving time rules. Timezones are read from the
+    /// system's zoneinfo database in `/usr/share/zoneinfo`, or the directory
+    /// set by the `TZDIR` environment variable. As with the `TZ` environment
+    /// variable, the name can also be an absolute path to a timezone file or
+    /// a POSIX `TZ` rule such as `EST5EDT,M3.2.0,M11.1.0`.
+    ///
+    /// `UTC`, `Etc/UTC`, `GMT` and `Etc/GMT` can be used without a zoneinfo
+    /// database (e.g. in slim containers). Windows doesn't have a zoneinfo
+    /// database, so only these names, POSIX `TZ` rules and paths to timezone
+    /// files are supported there.
+    ///
+    /// The timezone is loaded immediately, and errors are returned by
+    /// [`init`]. If the logger is used without calling `init()`, UTC is used
+    /// if the timezone couldn't be loaded.
+    ///
+    /// ```no_run
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// SimpleLogger::new().with_timezone("Europe/London").init().unwrap();
+    /// ```
+    ///
+    /// Use the timezone from the `TZ` environment variable:
+    ///
+    /// ```no_run
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// let timezone = std::env::var("TZ").unwrap_or_else(|_| "UTC".to_string());
+    ///
+    /// SimpleLogger::new().with_timezone(&timezone).init().unwrap();
+    /// ```
+    ///
+    /// This method is only available if the `timezones` feature is enabled.
+    ///
+    /// [`with_utc_offset`]: #method.with_utc_offset
+    /// [`init`]: #method.init
+    #[must_use = "You must call init() to begin logging"]
+    #[cfg(feature = "timezones")]
+    pub fn with_timezone(mut self, timezone: &str) -> SimpleLogger {
+        self.timestamps = Timestamps::Timezone(TimeZone::load(timezone));
+        self
     }
-}
 
-impl Log for SimpleLogger {
-    fn enabled(&self, metadata: &Metadata) -> bool {
-        &metadata.level().to_level_filter()
-            <= self
-                .module_levels
-                .iter()
-                /* At this point the Vec is already sorted so that we can simply take
-                 * the first match
-                 */
-                .find(|(name, _level)| metadata.target().starts_with(name))
-                .map(|(_name, level)| level)
-                .unwrap_or(&self.default_level)
+    /// Control whether messages are colored or not.
+    ///
+    /// This method is only available if the `colored` feature is enabled.
+    #[must_use = "You must call init() to begin logging"]
+    #[cfg(feature = "colors")]
+    pub fn with_colors(mut self, colors: bool) -> SimpleLogger {
+        self.colors = colors;
+        self
     }
 
-    fn log(&self, record: &Record) {
-        if self.enabled(record.metadata()) {
-            let level_string = {
-                #[cfg(feature = "colors")]
-                {
-                    if self.colors {
-                        match record.level() {
-                            Level::Error => format!("{:<5}", record.level().to_string()).red().to_string(),
-                            Level::Warn => format!("{:<5}", record.level().to_string()).yellow().to_string(),
-                            Level::Info => format!("{:<5}", record.level().to_string()).cyan().to_string(),
-                            Level::Debug => format!("{:<5}", record.level().to_string()).purple().to_string(),
-                            Level::Trace => format!("{:<5}", record.level().to_string()).normal().to_string(),
-                        }
-                    } else {
-                        format!("{:<5}", record.level().to_string())
-                    }
-                }
-                #[cfg(not(feature = "colors"))]
-                {
-                    format!("{:<5}", record.level().to_string())
-                }
-            };
-
-            let target = if !record.target().is_empty() {
-                record.target()
-            } else {
-                record.module_path().unwrap_or_default()
-            };
-
-            let thread = {
-                #[cfg(feature = "threads")]
-                if self.threads {
-                    let thread = std::thread::current();
+    /// Write each message as a JSON object on a single line ([JSON Lines](https://jsonlines.org/)).
+    ///
+    /// Objects contain the `timestamp` (if timestamps are enabled), `level`,
+    /// `target`, `thread` (if thread names are enabled), `module_path`,
+    /// `file`, `line` and `message` fields. Colors are never used.
+    ///
+    /// ```no_run
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// SimpleLogger::new().with_json().init().unwrap();
+    /// log::warn!("This is an example message.");
+    /// ```
+    ///
+    /// This outputs:
+    ///
+    /// ```txt
+    /// {"timestamp":"2026-10-16T17:27:07.013Z","level":"warn","target":"my_crate","module_path":"my_crate","file":"src/main.rs","line":5,"message":"This is an example message."}
+    /// ```
+    #[must_use = "You must call init() to begin logging"]
+    pub fn with_json(mut self) -> SimpleLogger {
+        self.format = Format::Json;
+        self
+    }
+
+    /// Write each message as [logfmt](https://brandur.org/logfmt) key/value pairs.
+    ///
+    /// Lines contain the `ts` (if timestamps are enabled), `level`, `target`,
+    /// `thread` (if thread names are enabled) and `msg` keys. Values that
+    /// contain spaces, quotes or `=` are quoted. Colors are never used.
+    ///
+    /// ```no_run
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// SimpleLogger::new().with_logfmt().init().unwrap();
+    /// log::warn!("This is an example message.");
+    /// ```
+    ///
+    /// This outputs:
+    ///
+    /// ```txt
+    /// ts=2026-10-16T17:27:07.013Z level=warn target=my_crate msg="This is an example message."
+    /// ```
+    #[must_use = "You must call init() to begin logging"]
+    pub fn with_logfmt(mut self) -> SimpleLogger {
+        self.format = Format::Logfmt;
+        self
+    }
+
+    /// Use a custom function to write each line.
+    ///
+    /// The function is given the parts of the line that would normally be
+    /// printed (see [`Context`]) and the record itself. It shouldn't write a
+    /// trailing newline. If it returns an error, the message is dropped.
+    ///
+    /// Reorder the default format and drop the thread name:
+    ///
+    /// ```no_run
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// SimpleLogger::new()
+    ///     .with_format(|w, ctx, record| write!(w, "{} {}: {}", ctx.level(), ctx.target(), record.args()))
+    ///     .init()
+    ///     .unwrap();
+    /// ```
+    #[must_use = "You must call init() to begin logging"]
+    pub fn with_format<F>(mut self, format: F) -> SimpleLogger
+    where
+        F: Fn(&mut dyn io::Write, &Context, &Record) -> io::Result<()> + Send + Sync + 'static,
+    {
+        self.format = Format::Custom(Box::new(format));
+        self
+    }
+
+    /// Use a template string to lay out each line.
+    ///
+    /// Placeholders are written as `{name}` or `{name:spec}`, where `spec` is
+    /// an optional alignment (`<`, `>` or `^`) followed by a width, and
+    /// `{{` and `}}` are used for literal braces. The available placeholders
+    /// are `timestamp`, `level`, `target`, `module_path`, `file`, `line`,
+    /// `thread` and `message`; placeholders for missing values (such as
+    /// `timestamp` when timestamps are disabled) are left empty.
+    ///
+    /// The template is validated by [`init`], which returns a descriptive
+    /// error for unknown placeholders. If the logger is used without calling
+    /// `init()`, an invalid template falls back to the default format.
+    ///
+    /// This makes it possible to read the format from configuration:
+    ///
+    /// ```no_run
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// let template = std::env::var("LOG_FORMAT").unwrap_or_else(|_| "{timestamp} {level:>5} {target}: {message}".into());
+    ///
+    /// SimpleLogger::new().with_format_template(&template).init().unwrap();
+    /// ```
+    ///
+    /// [`init`]: #method.init
+    #[must_use = "You must call init() to begin logging"]
+    pub fn with_format_template(mut self, template: &str) -> SimpleLogger {
+        self.format = Format::Template(Template::parse(template));
+        self
+    }
+
+    /// Choose the stream that messages are written to.
+    ///
+    /// Defaults to [`Output::Stdout`], or [`Output::Stderr`] if the `stderr`
+    /// feature is enabled. Use [`Output::Split`] to write errors and warnings
+    /// to stderr and everything else to stdout, like most command line tools.
+    ///
+    /// When the `colors` feature is enabled, colors are only used for
+    /// messages written to a stream that is a terminal.
+    ///
+    /// ```no_run
+    /// use simple_logger::{Output, SimpleLogger};
+    ///
+    /// SimpleLogger::new().with_output(Output::Stderr).init().unwrap();
+    /// ```
+    #[must_use = "You must call init() to begin logging"]
+    pub fn with_output(mut self, output: Output) -> SimpleLogger {
+        self.output = output;
+        self
+    }
+
+    /// Write messages to a file instead of stdout or stderr.
+    ///
+    /// The file is opened in append mode (and created if it doesn't exist)
+    /// by [`init`], which returns an error if the file can't be opened.
+    /// Colors are never written to the file.
+    ///
+    /// Lines are written as soon as they are complete, and [`Log::flush`]
+    /// flushes anything still buffered.
+    ///
+    /// ```no_run
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// SimpleLogger::new().with_output_file("app.log").init().unwrap();
+    /// ```
+    ///
+    /// [`init`]: #method.init
+    #[must_use = "You must call init() to begin logging"]
+    pub fn with_output_file<P: AsRef<Path>>(mut self, path: P) -> SimpleLogger {
+        self.file = Some(FileSink::new(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Control when the file set by [`with_output_file`] is rotated.
+    ///
+    /// Files are never rotated by default. Rotation happens while writing a
+    /// message, so messages from different threads are never lost or mixed
+    /// up across a rotation.
+    ///
+    /// When rotating files by time (`Rotation::Hourly` or `Rotation::Daily`,
+    /// which need the `timestamps` feature), the file a message is written to
+    /// is chosen using the same time and timezone as its timestamp (or UTC if
+    /// timestamps are disabled).
+    ///
+    /// Keep the 5 most recent files of up to 10MB each:
+    ///
+    /// ```no_run
+    /// use simple_logger::{Rotation, SimpleLogger};
+    ///
+    /// SimpleLogger::new()
+    ///     .with_output_file("app.log")
+    ///     .with_file_rotation(Rotation::Size { max_bytes: 10_000_000, keep: 5 })
+    ///     .init()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// Start a new file each day, using the local timezone (this requires the
+    /// `timestamps` feature):
+    ///
+    /// ```no_run
+    /// use simple_logger::{Rotation, SimpleLogger};
+    ///
+    /// # #[cfg(feature = "timestamps")]
+    /// SimpleLogger::new()
+    ///     .with_local_timestamps()
+    ///     .with_output_file("app.log")
+    ///     .with_file_rotation(Rotation::Daily)
+    ///     .init()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// [`with_output_file`]: #method.with_output_file
+    #[must_use = "You must call init() to begin logging"]
+    pub fn with_file_rotation(mut self, rotation: Rotation) -> SimpleLogger {
+        self.file_rotation = rotation;
+        self
+    }
+
+    /// Only log a fraction of the enabled messages at a level.
+    ///
+    /// `rate` is the probability of each message being logged, between `0.0`
+    /// (none) and `1.0` (all, the default). Sampling is done after messages
+    /// are filtered by level, so this is typically used to keep `Debug` or
+    /// `Trace` messages enabled in production while only writing some of them.
+    ///
+    /// Keep 10% of debug messages and 1% of trace messages:
+    ///
+    /// ```no_run
+    /// use log::{Level, LevelFilter};
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// SimpleLogger::new()
+    ///     .with_level(LevelFilter::Trace)
+    ///     .with_sampling(Level::Debug, 0.1)
+    ///     .with_sampling(Level::Trace, 0.01)
+    ///     .init()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// See [`with_sampling_seed`] to make sampling deterministic.
+    ///
+    /// If `rate` isn't between 0 and 1, [`init`] returns an error.
+    ///
+    /// [`with_sampling_seed`]: #method.with_sampling_seed
+    /// [`init`]: #method.init
+    #[must_use = "You must call init() to begin logging"]
+    pub fn with_sampling(mut self, level: Level, rate: f64) -> SimpleLogger {
+        self.sampler.get_or_insert_with(Sampler::default).set_level(level, rate);
+        self
+    }
+
+    /// Only log a fraction of the enabled messages at a level from some
+    /// specific modules.
+    ///
+    /// This overrides the rate set by [`with_sampling`] for a module and all
+    /// its sub-modules, matching modules in the same way as
+    /// [`with_module_level`] (including [`with_module_prefix_matching`]). When
+    /// rates are set for both a parent module and a child module, the more
+    /// specific rate is used.
+    ///
+    /// Keep all debug messages from `my_crate`, but only 10% from everything else:
+    ///
+    /// ```no_run
+    /// use log::Level;
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// SimpleLogger::new()
+    ///     .with_sampling(Level::Debug, 0.1)
+    ///     .with_module_sampling("my_crate", Level::Debug, 1.0)
+    ///     .init()
+    ///     .unwrap();
+    /// ```
+    ///
+    /// If `rate` isn't between 0 and 1, [`init`] returns an error.
+    ///
+    /// [`with_sampling`]: #method.with_sampling
+    /// [`init`]: #method.init
+    /// [`with_module_level`]: #method.with_module_level
+    /// [`with_module_prefix_matching`]: #method.with_module_prefix_matching
+    #[must_use = "You must call init() to begin logging"]
+    pub fn with_module_sampling(mut self, target: &str, level: Level, rate: f64) -> SimpleLogger {
+        self.sampler
+            .get_or_insert_with(Sampler::default)
+            .set_module_level(target, level, rate);
+        self
+    }
+
+    /// Seed the random number generator used for sampling.
+    ///
+    /// Sampling (see [`with_sampling`]) is random by default. With a fixed seed, the same messages
+    /// are kept each time the same sequence of messages is logged, which lets
+    /// tests assert exactly which messages are written.
+    ///
+    /// [`with_sampling`]: #method.with_sampling
+    #[must_use = "You must call init() to begin logging"]
+    pub fn with_sampling_seed(mut self, seed: u64) -> SimpleLogger {
+        self.sampler.get_or_insert_with(Sampler::default).set_seed(seed);
+        self
+    }
+
+    /// Limit how many messages each call site can log, to stop a hot loop
+    /// flooding the output.
+    ///
+    /// Each call site (a target, file and line) can log `max` messages per
+    /// `window`. Further messages are dropped, and a single `suppressed N
+    /// messages` line is logged for the call site in their place. There's no
+    /// timer, so this line isn't logged as soon as the window closes, but by
+    /// the next message logged after that from any call site, or by
+    /// [`Log::flush`]. Flush the logger before exiting so the last lines
+    /// aren't lost. Messages are only counted if they are enabled.
+    ///
+    /// Allow each call site to log 10 messages per second:
+    ///
+    /// ```no_run
+    /// use simple_logger::SimpleLogger;
+    /// use std::time::Duration;
+    ///
+    /// SimpleLogger::new().with_rate_limit(10, Duration::from_secs(1)).init().unwrap();
+    /// ```
+    #[must_use = "You must call init() to begin logging"]
+    pub fn with_rate_limit(mut self, max: usize, window: Duration) -> SimpleLogger {
+        self.rate_limit = Some(RateLimiter::new(max, window));
+        self
+    }
+
+    /// Collapse consecutive identical messages into one, like syslog.
+    ///
+    /// When a message has the same level, target and text as the previous
+    /// message, it is dropped. A single `last message repeated N times` line
+    /// is logged when a different message arrives or when [`Log::flush`] is
+    /// called. This keeps the output of retry loops readable.
+    ///
+    /// Repeated messages aren't counted by [`with_rate_limit`].
+    ///
+    /// ```no_run
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// SimpleLogger::new().with_dedup(true).init().unwrap();
+    /// ```
+    ///
+    /// [`with_rate_limit`]: #method.with_rate_limit
+    #[must_use = "You must call init() to begin logging"]
+    pub fn with_dedup(mut self, dedup: bool) -> SimpleLogger {
+        self.dedup = if dedup { Some(Deduplicator::default()) } else { None };
+        self
+    }
+
+    /// Call a function when a message can't be written.
+    ///
+    /// Messages that can't be written are dropped, and logging never panics.
+    /// Errors from writing to a closed pipe (e.g. when the output is piped
+    /// into `head`) are expected and always ignored. The first other error
+    /// (such as a full disk) is passed to this function, and later errors
+    /// are ignored so that they don't flood the program. Without an error
+    /// handler, all errors are ignored.
+    ///
+    /// ```no_run
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// SimpleLogger::new()
+    ///     .with_output_file("app.log")
+    ///     .with_error_handler(|err| eprintln!("Could not write log message: {}", err))
+    ///     .init()
+    ///     .unwrap();
+    /// ```
+    #[must_use = "You must call init() to begin logging"]
+    pub fn with_error_handler<F>(mut self, error_handler: F) -> SimpleLogger
+    where
+        F: Fn(&io::Error) + Send + Sync + 'static,
+    {
+        self.error_handler = Some(Box::new(error_handler));
+        self
+    }
+
+    /// Configure the logger
+    pub fn max_level(&self) -> LevelFilter {
+        levels::read(&self.levels).max_level()
+    }
+
+    /// 'Init' the actual logger and instantiate it,
+    /// this method MUST be called in order for the logger to be effective.
+    ///
+    /// This opens the file set by [`with_output_file`], if there is one, and
+    /// checks the template set by [`with_format_template`], the rates set by
+    /// [`with_sampling`] and [`with_module_sampling`], the regular expression
+    /// set by [`with_message_filter`], the timezone set by [`with_timezone`]
+    /// and the format set by [`with_timestamp_format_str`] are valid.
+    ///
+    /// [`with_output_file`]: #method.with_output_file
+    /// [`with_format_template`]: #method.with_format_template
+    /// [`with_sampling`]: #method.with_sampling
+    /// [`with_module_sampling`]: #method.with_module_sampling
+    /// [`with_message_filter`]: #method.with_message_filter
+    /// [`with_timezone`]: #method.with_timezone
+    /// [`with_timestamp_format_str`]: #method.with_timestamp_format_str
+    pub fn init(self) -> Result<(), InitError> {
+        if let Format::Template(Err(err)) = &self.format {
+            return Err(InitError::Template(err.clone()));
+        }
+
+        if let Some(rate) = self.sampler.as_ref().and_then(Sampler::invalid_rate) {
+            return Err(InitError::SamplingRate(rate));
+        }
+
+        #[cfg(feature = "regex")]
+        if let Some(Err(err)) = &levels::read(&self.levels).message_filter {
+            return Err(InitError::MessageFilter(err.clone()));
+        }
+
+        #[cfg(feature = "timezones")]
+        if let Timestamps::Timezone(Err(err)) = &self.timestamps {
+            return Err(InitError::Timezone(err.clone()));
+        }
+
+        #[cfg(feature = "timestamps")]
+        if let Some(Err(err)) = &self.timestamps_format {
+            return Err(InitError::TimestampFormat(err.clone()));
+        }
+
+        if let Some(file) = &self.file {
+            #[cfg(feature = "timestamps")]
+            let period = self.file_rotation.period(self.now());
+            #[cfg(not(feature = "timestamps"))]
+            let period: Option<String> = None;
+
+            file.open(period.as_deref()).map_err(|source| InitError::OpenFile {
+                path: file.path().to_path_buf(),
+                source,
+            })?;
+        }
+
+        Ok(self.set_logger()?)
+    }
+
+    /// The current time, in the timezone used for timestamps.
+    ///
+    /// UTC is used when timestamps are disabled.
+    #[cfg(feature = "timestamps")]
+    fn now(&self) -> OffsetDateTime {
+        match &self.timestamps {
+            Timestamps::None | Timestamps::Utc | Timestamps::Elapsed | Timestamps::Delta | Timestamps::Epoch(_) => {
+                OffsetDateTime::now_utc()
+            }
+            Timestamps::Local => OffsetDateTime::now_utc().to_offset(self.local_offset()),
+            Timestamps::UtcOffset(offset) => OffsetDateTime::now_utc().to_offset(*offset),
+            #[cfg(feature = "timezones")]
+            Timestamps::Timezone(Err(_)) => OffsetDateTime::now_utc(),
+            #[cfg(feature = "timezones")]
+            Timestamps::Timezone(Ok(timezone)) => {
+                let now = OffsetDateTime::now_utc();
+                now.to_offset(timezone.offset_at(now.unix_timestamp()))
+            }
+        }
+    }
+
+    /// Format a timestamp using the format set by [`with_timestamp_format`](#method.with_timestamp_format), or a
+    /// default format.
+    #[cfg(feature = "timestamps")]
+    fn format_timestamp(&self, now: OffsetDateTime, default: &[FormatItem]) -> String {
+        match &self.timestamps_format {
+            Some(Ok(format)) => now.format(format),
+            _ => now.format(default),
+        }
+        .unwrap()
+    }
+
+    /// The local UTC offset, which is looked up the first time it is needed and then reused.
+    ///
+    /// If the offset can't be determined, the fallback offset is used and a warning is logged.
+    #[cfg(feature = "timestamps")]
+    fn local_offset(&self) -> UtcOffset {
+        self.local_offset_with(UtcOffset::current_local_offset)
+    }
+
+    #[cfg(feature = "timestamps")]
+    fn local_offset_with<F>(&self, current_local_offset: F) -> UtcOffset
+    where
+        F: FnOnce() -> Result<UtcOffset, IndeterminateOffset>,
+    {
+        let mut fell_back = false;
+        let offset = *self.local_offset.get_or_init(|| {
+            current_local_offset().unwrap_or_else(|_| {
+                fell_back = true;
+                self.local_offset_fallback
+            })
+        });
+
+        let metadata = Metadata::builder()
+            .level(Level::Warn)
+            .target(env!("CARGO_PKG_NAME"))
+            .build();
+
+        if fell_back && self.enabled(&metadata) {
+            self.write_record(
+                &Record::builder()
+                    .args(format_args!(
+                        "Could not determine the local UTC offset, using {} for timestamps instead",
+                        offset
+                    ))
+                    .metadata(metadata)
+                    .module_path_static(Some(module_path!()))
+                    .build(),
+            );
+        }
+
+        offset
+    }
+
+    /// 'Init' the logger in the same way as [`init`], returning a [`Handle`]
+    /// that can change the log levels while the program is running.
+    ///
+    /// ```no_run
+    /// use log::LevelFilter;
+    /// use simple_logger::SimpleLogger;
+    ///
+    /// let handle = SimpleLogger::new().with_level(LevelFilter::Info).init_with_handle().unwrap();
+    /// log::debug!("This will NOT be logged.");
+    ///
+    /// handle.set_level(LevelFilter::Debug);
+    /// log::debug!("This will be logged.");
+    /// ```
+    ///
+    /// [`init`]: #method.init
+    pub fn init_with_handle(self) -> Result<Handle, InitError> {
+        let handle = Handle {
+            levels: self.levels.clone(),
+        };
+
+        self.init()?;
+        Ok(handle)
+    }
+
+    /// Set this as the global logger, without any of the checks done by [`init`](#method.init).
+    #[cfg_attr(not(any(feature = "timestamps", feature = "colors")), allow(unused_mut))]
+    fn set_logger(mut self) -> Result<(), SetLoggerError> {
+        // Look up the local offset before the program starts any threads, as it can't be done safely after that.
+        #[cfg(feature = "timestamps")]
+        if self.timestamps == Timestamps::Local {
+            self.local_offset();
+        }
+
+        #[cfg(feature = "timestamps")]
+        {
+            self.start = Instant::now();
+            *self.previous.get_mut().unwrap_or_else(PoisonError::into_inner) = self.start;
+        }
+
+        #[cfg(feature = "colors")]
+        {
+            use std::io::{stderr, stdout, IsTerminal};
+
+            self.stdout_colors = colors_enabled(stdout().is_terminal(), |name| std::env::var_os(name));
+            self.stderr_colors = colors_enabled(stderr().is_terminal(), |name| std::env::var_os(name));
+        }
+
+        #[cfg(all(windows, feature = "colored"))]
+        set_up_windows_color_terminal();
+
+        // The colored crate only checks stdout, so override it when stderr can be colored, and check the stream each
+        // message is written to in `write_record`.
+        #[cfg(feature = "colored")]
+        match self.output {
+            Output::Stdout => {}
+            Output::Stderr if self.stderr_colors => colored::control::set_override(true),
+            Output::Split if self.stdout_colors || self.stderr_colors => colored::control::set_override(true),
+            _ => {}
+        }
+
+        log::set_max_level(self.max_level());
+        log::set_boxed_logger(Box::new(self))
+    }
+
+    /// Check if messages written to a stream can be colored, using the result decided by [`init`](#method.init).
+    #[cfg(feature = "colors")]
+    fn stream_colors(&self, stream: Output) -> bool {
+        match stream {
+            Output::Stderr => self.stderr_colors,
+            _ => self.stdout_colors,
+        }
+    }
+
+    /// Format a record and write it to the output, without checking if it is enabled.
+    fn write_record(&self, record: &Record) {
+        let stream = self.output.stream(record.level());
+
+        // The colored crate only checks stdout, so use the decision made by `init()` for other streams.
+        #[cfg(feature = "colors")]
+        let colors = self.colors
+            && matches!(self.format, Format::Text | Format::Custom(_) | Format::Template(_))
+            && self.file.is_none()
+            && (self.output == Output::Stdout || self.stream_colors(stream));
+
+        let color_level = |level_string: String| -> String {
+            #[cfg(feature = "colors")]
+            if colors {
+                return match record.level() {
+                    Level::Error => level_string.red().to_string(),
+                    Level::Warn => level_string.yellow().to_string(),
+                    Level::Info => level_string.cyan().to_string(),
+                    Level::Debug => level_string.purple().to_string(),
+                    Level::Trace => level_string.normal().to_string(),
+                };
+            }
+
+            level_string
+        };
 
-                    format!("@{}", {
+        let level_string = color_level(format!("{:<5}", record.level().to_string()));
+
+        let target = if !record.target().is_empty() {
+            record.target()
+        } else {
+            record.module_path().unwrap_or_default()
+        };
+
+        let thread: Option<String> = {
+            #[cfg(feature = "threads")]
+            if self.threads {
+                let thread = std::thread::current();
+
+                Some(
+                    {
                         #[cfg(feature = "nightly")]
                         {
                             thread.name().unwrap_or(&thread.id().as_u64().to_string())
@@ -424,70 +1494,340 @@ impl Log for SimpleLogger {
                         {
                             thread.name().unwrap_or("?")
                         }
-                    })
-                } else {
-                    "".to_string()
-                }
+                    }
+                    .to_string(),
+                )
+            } else {
+                None
+            }
 
-                #[cfg(not(feature = "threads"))]
-                ""
-            };
+            #[cfg(not(feature = "threads"))]
+            None
+        };
 
-            let timestamp = {
-                #[cfg(feature = "timestamps")]
-                match self.timestamps {
-                    Timestamps::None => "".to_string(),
-                    Timestamps::Local => format!(
-                        "{} ",
-                        OffsetDateTime::now_local()
-                            .expect(concat!(
-                                "Could not determine the UTC offset on this system. ",
-                                "Consider displaying UTC time instead. ",
-                                "Possible causes are that the time crate does not implement \"local_offset_at\" ",
-                                "on your system, or that you are running in a multi-threaded environment and ",
-                                "the time crate is returning \"None\" from \"local_offset_at\" to avoid unsafe ",
-                                "behaviour. See the time crate's documentation for more information. ",
-                                "(https://time-rs.github.io/internal-api/time/index.html#feature-flags)"
-                            ))
-                            .format(&self.timestamps_format.unwrap_or(TIMESTAMP_FORMAT_OFFSET))
-                            .unwrap()
-                    ),
-                    Timestamps::Utc => format!(
-                        "{} ",
-                        OffsetDateTime::now_utc()
-                            .format(&self.timestamps_format.unwrap_or(TIMESTAMP_FORMAT_UTC))
-                            .unwrap()
-                    ),
-                    Timestamps::UtcOffset(offset) => format!(
-                        "{} ",
-                        OffsetDateTime::now_utc()
-                            .to_offset(offset)
-                            .format(&self.timestamps_format.unwrap_or(TIMESTAMP_FORMAT_OFFSET))
-                            .unwrap()
-                    ),
+        // The same time is used for the timestamp and for choosing a file when rotating by time.
+        #[cfg(feature = "timestamps")]
+        let now = self.now();
+
+        let timestamp: Option<String> = {
+            #[cfg(feature = "timestamps")]
+            match self.timestamps {
+                Timestamps::None => None,
+                Timestamps::Local | Timestamps::UtcOffset(_) => {
+                    Some(self.format_timestamp(now, TIMESTAMP_FORMAT_OFFSET))
+                }
+                #[cfg(feature = "timezones")]
+                Timestamps::Timezone(_) => Some(self.format_timestamp(now, TIMESTAMP_FORMAT_OFFSET)),
+                Timestamps::Utc => Some(self.format_timestamp(now, TIMESTAMP_FORMAT_UTC)),
+                Timestamps::Elapsed => Some(format_duration(self.start.elapsed())),
+                Timestamps::Epoch(precision) => Some(precision.format(now)),
+                Timestamps::Delta => {
+                    let now = Instant::now();
+                    let mut previous = self.previous.lock().unwrap_or_else(PoisonError::into_inner);
+                    let delta = now.saturating_duration_since(*previous);
+                    *previous = now;
+                    Some(format_duration(delta))
                 }
+            }
 
+            #[cfg(not(feature = "timestamps"))]
+            None
+        };
+
+        let message = match &self.format {
+            Format::Text | Format::Template(Err(_)) => format!(
+                "{}{} [{}{}] {}",
+                timestamp.map(|timestamp| timestamp + " ").unwrap_or_default(),
+                level_string,
+                target,
+                thread.map(|thread| format!("@{}", thread)).unwrap_or_default(),
+                record.args()
+            ),
+            Format::Json => {
+                #[cfg(feature = "timestamps")]
+                let numeric = matches!(self.timestamps, Timestamps::Epoch(_));
                 #[cfg(not(feature = "timestamps"))]
-                ""
+                let numeric = false;
+
+                let timestamp = timestamp.as_deref().map(|timestamp| match numeric {
+                    true => format::Timestamp::Number(timestamp),
+                    false => format::Timestamp::Text(timestamp),
+                });
+
+                format::json(timestamp, record, target, thread.as_deref())
+            }
+            Format::Logfmt => format::logfmt(timestamp.as_deref(), record, target, thread.as_deref()),
+            Format::Template(Ok(template)) => {
+                let values = template::Values {
+                    timestamp: timestamp.as_deref(),
+                    target,
+                    thread: thread.as_deref(),
+                };
+
+                template.render(record, &values, &color_level)
+            }
+            Format::Custom(format) => {
+                let context = Context {
+                    timestamp: timestamp.as_deref(),
+                    level: &level_string,
+                    target,
+                    thread: thread.as_deref(),
+                };
+
+                match format::custom(format.as_ref(), &context, record) {
+                    Some(message) => message,
+                    None => return,
+                }
+            }
+        };
+
+        if let Some(file) = &self.file {
+            #[cfg(feature = "timestamps")]
+            let period = self.file_rotation.period(now);
+            #[cfg(not(feature = "timestamps"))]
+            let period: Option<String> = None;
+
+            if let Err(err) = file.write_line(&message, self.file_rotation, period.as_deref()) {
+                self.report_error(err);
+            }
+            return;
+        }
+
+        // Unlike println!() and eprintln!(), this doesn't panic if the stream is closed (e.g. when piped into `head`).
+        let result = match stream {
+            Output::Stderr => writeln!(io::stderr().lock(), "{}", message),
+            _ => writeln!(io::stdout().lock(), "{}", message),
+        };
+
+        if let Err(err) = result {
+            self.report_error(err);
+        }
+    }
+
+    /// Pass an error from writing a message to the error handler, if this is the first error.
+    ///
+    /// Broken pipes are expected when the output is piped into a program that exits early, and are always ignored.
+    fn report_error(&self, err: io::Error) {
+        if err.kind() == io::ErrorKind::BrokenPipe {
+            return;
+        }
+
+        if let Some(error_handler) = &self.error_handler {
+            if !self.error_reported.swap(true, Ordering::Relaxed) {
+                error_handler(&err);
+            }
+        }
+    }
+
+    /// Write a summary of the repeats of a record dropped by deduplication.
+    fn write_repeated(&self, repeated: &Repeated) {
+        self.write_record(
+            &Record::builder()
+                .args(format_args!("last message repeated {} times", repeated.count))
+                .level(repeated.level)
+                .target(&repeated.target)
+                .module_path(repeated.module_path.as_deref())
+                .file(repeated.file.as_deref())
+                .line(repeated.line)
+                .build(),
+        );
+    }
+
+    /// Write a summary of the records suppressed from a call site by the rate limit.
+    fn write_suppressed(&self, suppressed: &Suppressed) {
+        self.write_record(
+            &Record::builder()
+                .args(format_args!("suppressed {} messages", suppressed.count))
+                .level(suppressed.level)
+                .target(&suppressed.target)
+                .module_path(suppressed.module_path.as_deref())
+                .file(suppressed.file.as_deref())
+                .line(suppressed.line)
+                .build(),
+        );
+    }
+}
+
+impl Default for SimpleLogger {
+    /// See [this](struct.SimpleLogger.html#method.new)
+    fn default() -> Self {
+        SimpleLogger::new()
+    }
+}
+
+impl Log for SimpleLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level().to_level_filter() <= levels::read(&self.levels).cached_level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            #[cfg(feature = "regex")]
+            if !levels::read(&self.levels).matches_message(record) {
+                return;
+            }
+
+            if let Some(sampler) = &self.sampler {
+                if !sampler.keep(record, levels::read(&self.levels).prefix_matching) {
+                    return;
+                }
+            }
+
+            if let Some(dedup) = &self.dedup {
+                let (allowed, repeated) = dedup.check(record);
+
+                if let Some(repeated) = repeated {
+                    self.write_repeated(&repeated);
+                }
+
+                if !allowed {
+                    return;
+                }
+            }
+
+            if let Some(rate_limit) = &self.rate_limit {
+                let (allowed, summaries) = rate_limit.check(record, Instant::now());
+
+                for suppressed in summaries {
+                    self.write_suppressed(&suppressed);
+                }
+
+                if !allowed {
+                    return;
+                }
+            }
+
+            self.write_record(record);
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(repeated) = self.dedup.as_ref().and_then(Deduplicator::flush) {
+            self.write_repeated(&repeated);
+        }
+
+        if let Some(rate_limit) = &self.rate_limit {
+            for suppressed in rate_limit.flush() {
+                self.write_suppressed(&suppressed);
+            }
+        }
+
+        if let Some(file) = &self.file {
+            if let Err(err) = file.flush() {
+                self.report_error(err);
+            }
+        }
+    }
+}
+
+/// Log levels parsed from a `RUST_LOG`-style specification.
+///
+/// See [`SimpleLogger::env`] for the supported syntax.
+#[derive(Debug, Default, PartialEq)]
+struct Directives {
+    /// The default level, if one was given.
+    level: Option<LevelFilter>,
+
+    /// Levels for specific modules, in the order they were given.
+    module_levels: Vec<(String, LevelFilter)>,
+
+    /// The regular expression after a `/`, if one was given.
+    #[cfg_attr(not(feature = "regex"), allow(dead_code))]
+    message_filter: Option<String>,
+}
+
+impl Directives {
+    fn parse(spec: &str) -> Directives {
+        let mut directives = Directives::default();
+
+        let spec = match spec.split_once('/') {
+            Some((spec, filter)) => {
+                directives.message_filter = Some(filter.to_string());
+                spec
+            }
+            None => spec,
+        };
+
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let mut parts = directive.splitn(2, '=');
+            let (name, level) = match (parts.next(), parts.next()) {
+                (Some(name), Some(level)) => (name.trim(), level.trim()),
+                (Some(name), None) => (name.trim(), ""),
+                (None, _) => continue,
             };
 
-            let message = format!("{}{} [{}{}] {}", timestamp, level_string, target, thread, record.args());
+            if level.is_empty() {
+                // A bare word is either a default level or a module to enable completely.
+                match LevelFilter::from_str(name) {
+                    Ok(level) => directives.level = Some(level),
+                    Err(_) => directives.module_levels.push((name.to_string(), LevelFilter::Trace)),
+                }
+            } else if let Ok(level) = LevelFilter::from_str(level) {
+                if !name.is_empty() {
+                    directives.module_levels.push((name.to_string(), level));
+                }
+            }
+        }
+
+        directives
+    }
+
+    /// Parse directives from a file, with any number of comma separated
+    /// directives on each line. Blank lines and lines starting with `#` are
+    /// skipped.
+    ///
+    /// A `/` message filter runs to the end of its line, so it is only allowed
+    /// on the last line. If an earlier line has one, the number of that line is
+    /// returned as an error.
+    #[cfg(all(unix, feature = "signals"))]
+    fn parse_lines(contents: &str) -> Result<Directives, usize> {
+        let lines: Vec<(usize, &str)> = contents
+            .lines()
+            .map(str::trim)
+            .enumerate()
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .collect();
+
+        let mut directives = Directives::default();
+
+        for (index, (number, line)) in lines.iter().enumerate() {
+            let parsed = Directives::parse(line);
+
+            if parsed.message_filter.is_some() {
+                if index + 1 < lines.len() {
+                    return Err(number + 1);
+                }
+
+                directives.message_filter = parsed.message_filter;
+            }
 
-            #[cfg(not(feature = "stderr"))]
-            println!("{}", message);
+            if parsed.level.is_some() {
+                directives.level = parsed.level;
+            }
 
-            #[cfg(feature = "stderr")]
-            eprintln!("{}", message);
+            directives.module_levels.extend(parsed.module_levels);
         }
+
+        Ok(directives)
+    }
+
+    /// The message filter, or `None` if there isn't one.
+    #[cfg(feature = "regex")]
+    fn message_filter(&self) -> Option<Result<regex::Regex, regex::Error>> {
+        self.message_filter.as_deref().map(regex::Regex::new)
     }
+}
 
-    fn flush(&self) {}
+/// Format a duration like `+12.345s`, for elapsed and delta timestamps.
+#[cfg(feature = "timestamps")]
+fn format_duration(duration: Duration) -> String {
+    format!("+{}.{:03}s", duration.as_secs(), duration.subsec_millis())
 }
 
 /// Configure the console to display colours.
 ///
 /// This is only needed on Windows when using the 'colors' feature.
-/// It doesn't currently handle combining the 'colors' and 'stderr' features.
+/// It doesn't currently handle writing colors to stderr.
 #[cfg(all(windows, feature = "colors"))]
 pub fn set_up_windows_color_terminal() {
     use std::io::{stdout, IsTerminal};
@@ -517,13 +1857,22 @@ pub fn set_up_windows_color_terminal() {
     }
 }
 
-/// The colored crate will disable colors when STDOUT is not a terminal. This method overrides this
-/// behaviour to check the status of STDERR instead.
-#[cfg(all(feature = "colored", feature = "stderr"))]
-fn use_stderr_for_colors() {
-    use std::io::{stderr, IsTerminal};
+/// Decide whether messages written to a stream can be colored, following the same rules as the colored crate:
+/// `CLICOLOR_FORCE` (unless it is `0`) enables colors, then `NO_COLOR` disables them, then colors are used if the
+/// stream is a terminal and `CLICOLOR` isn't `0`.
+#[cfg(feature = "colors")]
+fn colors_enabled<F: Fn(&str) -> Option<OsString>>(is_terminal: bool, var: F) -> bool {
+    let flag = |name: &str| var(name).map(|value| value != "0");
+
+    if flag("CLICOLOR_FORCE") == Some(true) {
+        return true;
+    }
+
+    if flag("NO_COLOR").is_some() {
+        return false;
+    }
 
-    colored::control::set_override(stderr().is_terminal());
+    flag("CLICOLOR").unwrap_or(true) && is_terminal
 }
 
 /// Initialise the logger with its default configuration.
@@ -531,7 +1880,7 @@ fn use_stderr_for_colors() {
 /// Log messages will not be filtered.
 /// The `RUST_LOG` environment variable is not used.
 pub fn init() -> Result<(), SetLoggerError> {
-    SimpleLogger::new().init()
+    SimpleLogger::new().set_logger()
 }
 
 /// Initialise the logger with its default configuration.
@@ -542,7 +1891,7 @@ pub fn init() -> Result<(), SetLoggerError> {
 /// This function is only available if the `timestamps` feature is enabled.
 #[cfg(feature = "timestamps")]
 pub fn init_utc() -> Result<(), SetLoggerError> {
-    SimpleLogger::new().with_utc_timestamps().init()
+    SimpleLogger::new().with_utc_timestamps().set_logger()
 }
 
 /// Initialise the logger with the `RUST_LOG` environment variable.
@@ -550,11 +1899,30 @@ pub fn init_utc() -> Result<(), SetLoggerError> {
 /// Log messages will be filtered based on the `RUST_LOG` environment variable.
 ///
 /// This will use the default level ([`LevelFilter::Trace`]) if the `RUST_LOG`
-/// environment variable is not set or can't be parsed as a standard log level
-/// (see [`LevelFilter::from_str`] and [`log::LOG_LEVEL_NAMES`] for valid
-/// names).
+/// environment variable is not set or doesn't contain a default level (see
+/// [`SimpleLogger::env`] for the supported syntax, and
+/// [`LevelFilter::from_str`] and [`log::LOG_LEVEL_NAMES`] for valid names).
+///
+/// Unlike [`SimpleLogger::init`], this can't return an error for an invalid
+/// `/regex` message filter in `RUST_LOG`. Instead, a warning is logged and
+/// messages aren't filtered.
 pub fn init_with_env() -> Result<(), SetLoggerError> {
-    SimpleLogger::new().env().init()
+    let logger = SimpleLogger::new().env();
+
+    #[cfg(feature = "regex")]
+    let invalid_filter = match &levels::read(&logger.levels).message_filter {
+        Some(Err(err)) => Some(err.clone()),
+        _ => None,
+    };
+
+    logger.set_logger()?;
+
+    #[cfg(feature = "regex")]
+    if let Some(err) = invalid_filter {
+        log::warn!("Ignoring the invalid log message filter in RUST_LOG: {}", err);
+    }
+
+    Ok(())
 }
 
 /// Initialise the logger with a specific log level.
@@ -562,7 +1930,7 @@ pub fn init_with_env() -> Result<(), SetLoggerError> {
 /// Log messages below the given [`Level`] will be filtered.
 /// The `RUST_LOG` environment variable is not used.
 pub fn init_with_level(level: Level) -> Result<(), SetLoggerError> {
-    SimpleLogger::new().with_level(level.to_level_filter()).init()
+    SimpleLogger::new().with_level(level.to_level_filter()).set_logger()
 }
 
 /// Use [`init_with_env`] instead.
@@ -577,8 +1945,10 @@ pub fn init_by_env() {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison, clippy::bool_comparison)]
 mod test {
     use super::*;
+    use file::TempFile;
 
     #[test]
     fn test_module_levels_allowlist() {
@@ -670,6 +2040,105 @@ mod test {
         assert!(builder.timestamps_format.is_some());
     }
 
+    #[test]
+    #[cfg(feature = "timestamps")]
+    fn test_epoch_precision() {
+        let now = time::macros::datetime!(2026-10-16 12:00:00.123_456_789 UTC);
+        assert_eq!(EpochPrecision::Seconds.format(now), "1792152000");
+        assert_eq!(EpochPrecision::Millis.format(now), "1792152000123");
+        assert_eq!(EpochPrecision::Micros.format(now), "1792152000123456");
+        assert_eq!(EpochPrecision::Nanos.format(now), "1792152000123456789");
+
+        let before = time::macros::datetime!(1969-12-31 23:59:59.5 UTC);
+        assert_eq!(EpochPrecision::Seconds.format(before), "-1");
+    }
+
+    #[test]
+    #[cfg(feature = "timestamps")]
+    fn test_with_epoch_timestamps_json() {
+        let line = capture(
+            SimpleLogger::new()
+                .with_epoch_timestamps(EpochPrecision::Seconds)
+                .with_json(),
+            |logger| {
+                logger.log(
+                    &Record::builder()
+                        .args(format_args!("a message"))
+                        .level(Level::Info)
+                        .build(),
+                );
+            },
+        );
+
+        let timestamp = line
+            .strip_prefix(r#"{"timestamp":"#)
+            .and_then(|rest| rest.split(',').next())
+            .unwrap();
+        assert!(timestamp.parse::<i64>().is_ok(), "{}", line);
+    }
+
+    #[test]
+    #[cfg(feature = "timestamps")]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(12_345)), "+12.345s");
+        assert_eq!(format_duration(Duration::from_micros(1_999)), "+0.001s");
+        assert_eq!(format_duration(Duration::from_secs(3600)), "+3600.000s");
+    }
+
+    #[test]
+    #[cfg(feature = "timestamps")]
+    fn test_with_delta_timestamps() {
+        let output = capture(
+            SimpleLogger::new()
+                .with_delta_timestamps()
+                .with_format_template("{timestamp} {message}"),
+            |logger| {
+                *logger.previous.lock().unwrap() -= Duration::from_secs(5);
+                logger.log(&Record::builder().args(format_args!("first")).level(Level::Info).build());
+                logger.log(
+                    &Record::builder()
+                        .args(format_args!("second"))
+                        .level(Level::Info)
+                        .build(),
+                );
+            },
+        );
+
+        let lines = output.lines().collect::<Vec<_>>();
+        assert!(
+            lines[0].starts_with("+5.") && lines[0].ends_with("s first"),
+            "{}",
+            lines[0]
+        );
+        assert!(
+            lines[1].starts_with("+0.") && lines[1].ends_with("s second"),
+            "{}",
+            lines[1]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "timestamps")]
+    fn test_with_timestamp_format_str() {
+        let logger = SimpleLogger::new()
+            .with_utc_timestamps()
+            .with_timestamp_format_str("[year]/[month]/[day]");
+        let now = time::macros::datetime!(2026-10-16 12:00 UTC);
+        assert_eq!(logger.format_timestamp(now, TIMESTAMP_FORMAT_UTC), "2026/10/16");
+
+        let logger = SimpleLogger::new()
+            .with_utc_timestamps()
+            .with_timestamp_format_str("[year] [nope]");
+        assert_eq!(
+            logger.format_timestamp(now, TIMESTAMP_FORMAT_UTC),
+            "2026-10-16T12:00:00.000Z"
+        );
+
+        let err = logger.init().unwrap_err();
+        assert!(matches!(err, InitError::TimestampFormat(_)));
+        assert!(err.to_string().contains("nope"), "{}", err);
+    }
+
     #[test]
     #[cfg(feature = "colored")]
     fn test_with_colors() {
@@ -680,6 +2149,329 @@ mod test {
         assert!(builder.colors == false);
     }
 
+    #[test]
+    #[cfg(feature = "colors")]
+    fn test_colors_enabled() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(var, _)| *var == name)
+                    .map(|(_, value)| OsString::from(value))
+            }
+        };
+
+        assert!(colors_enabled(true, env(&[])));
+        assert!(!colors_enabled(false, env(&[])));
+        assert!(!colors_enabled(true, env(&[("NO_COLOR", "1")])));
+        assert!(!colors_enabled(true, env(&[("NO_COLOR", "")])));
+        assert!(!colors_enabled(true, env(&[("CLICOLOR", "0")])));
+        assert!(colors_enabled(false, env(&[("CLICOLOR_FORCE", "1")])));
+        assert!(colors_enabled(true, env(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")])));
+        assert!(!colors_enabled(
+            true,
+            env(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "0")])
+        ));
+    }
+
+    #[test]
+    fn test_with_output() {
+        let builder = SimpleLogger::new();
+        assert_eq!(builder.output, Output::default());
+
+        let builder = builder.with_output(Output::Stderr);
+        assert_eq!(builder.output, Output::Stderr);
+    }
+
+    #[test]
+    fn test_output_split() {
+        assert_eq!(Output::Split.stream(Level::Error), Output::Stderr);
+        assert_eq!(Output::Split.stream(Level::Warn), Output::Stderr);
+        assert_eq!(Output::Split.stream(Level::Info), Output::Stdout);
+        assert_eq!(Output::Split.stream(Level::Trace), Output::Stdout);
+        assert_eq!(Output::Stdout.stream(Level::Error), Output::Stdout);
+        assert_eq!(Output::Stderr.stream(Level::Trace), Output::Stderr);
+    }
+
+    #[test]
+    fn test_output_default() {
+        #[cfg(feature = "stderr")]
+        assert_eq!(Output::default(), Output::Stderr);
+
+        #[cfg(not(feature = "stderr"))]
+        assert_eq!(Output::default(), Output::Stdout);
+    }
+
+    #[test]
+    fn test_with_output_file() {
+        let contents = capture(SimpleLogger::new(), |logger| {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("written to a file"))
+                    .level(Level::Error)
+                    .target("my_crate")
+                    .build(),
+            );
+        });
+
+        assert!(contents.ends_with("ERROR [my_crate] written to a file\n"));
+        assert!(!contents.contains('\x1b'));
+    }
+
+    #[test]
+    fn test_with_json() {
+        let contents = capture(SimpleLogger::new().with_json(), |logger| {
+            logger.log(
+                &Record::builder()
+                    .args(format_args!("written as json"))
+                    .level(Level::Info)
+                    .target("my_crate")
+                    .build(),
+            );
+        });
+
+        assert!(contents.starts_with('{'));
+        assert!(contents.ends_with(
+            r#""level":"info","target":"my_crate","module_path":null,"file":null,"line":null,"message":"written as json"}
+"#
+        ));
+    }
+
+    #[test]
+    fn test_with_format() {
+        let output = capture(
+            SimpleLogger::new()
+                .with_format(|w, ctx, record| write!(w, "{}|{}|{}", ctx.target(), ctx.level(), record.args())),
+            |logger| {
+                logger.log(
+                    &Record::builder()
+                        .args(format_args!("custom"))
+                        .level(Level::Warn)
+                        .target("my_crate")
+                        .build(),
+                );
+            },
+        );
+
+        assert_eq!(output, "my_crate|WARN |custom\n");
+    }
+
+    #[test]
+    fn test_with_format_template() {
+        let output = capture(
+            SimpleLogger::new().with_format_template("{level:>5} {target}: {message}"),
+            |logger| {
+                logger.log(
+                    &Record::builder()
+                        .args(format_args!("templated"))
+                        .level(Level::Info)
+                        .target("my_crate")
+                        .build(),
+                );
+            },
+        );
+
+        assert_eq!(output, " INFO my_crate: templated\n");
+    }
+
+    #[test]
+    fn test_with_format_template_invalid() {
+        let result = SimpleLogger::new().with_format_template("{level} {unknown}").init();
+        assert!(matches!(result, Err(InitError::Template(_))));
+    }
+
+    #[test]
+    fn test_with_sampling_invalid_rate() {
+        let result = SimpleLogger::new().with_sampling(Level::Debug, 1.5).init();
+        assert!(matches!(result, Err(InitError::SamplingRate(rate)) if rate == 1.5));
+
+        let result = SimpleLogger::new()
+            .with_module_sampling("my_crate", Level::Debug, -0.5)
+            .init();
+        assert!(matches!(result, Err(InitError::SamplingRate(rate)) if rate == -0.5));
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_with_message_filter() {
+        let output = capture(
+            SimpleLogger::new()
+                .with_format_template("{message}")
+                .with_message_filter(r"req-\d+"),
+            |logger| {
+                for message in ["handling req-42", "unrelated", "done with req-42"] {
+                    logger.log(
+                        &Record::builder()
+                            .args(format_args!("{}", message))
+                            .level(Level::Info)
+                            .build(),
+                    );
+                }
+            },
+        );
+
+        assert_eq!(output, "handling req-42\ndone with req-42\n");
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_with_message_filter_invalid() {
+        let result = SimpleLogger::new().with_message_filter("req-(").init();
+        assert!(matches!(result, Err(InitError::MessageFilter(_))));
+    }
+
+    #[test]
+    fn test_with_rate_limit() {
+        let output = capture(
+            SimpleLogger::new()
+                .with_format_template("{level} {message}")
+                .with_rate_limit(1, Duration::from_secs(60)),
+            |logger| {
+                for n in 0..3 {
+                    logger.log(
+                        &Record::builder()
+                            .args(format_args!("retrying {}", n))
+                            .level(Level::Warn)
+                            .file(Some("src/main.rs"))
+                            .line(Some(7))
+                            .build(),
+                    );
+                }
+            },
+        );
+
+        assert_eq!(output, "WARN retrying 0\nWARN suppressed 2 messages\n");
+    }
+
+    #[test]
+    #[cfg(feature = "timestamps")]
+    fn test_local_offset_fallback() {
+        let output = capture(
+            SimpleLogger::new()
+                .with_local_timestamps()
+                .with_local_offset_fallback(time::macros::offset!(+02:00))
+                .with_timestamp_format(time::macros::format_description!("[offset_hour sign:mandatory]"))
+                .with_format_template("{timestamp} {level} {message}"),
+            |logger| {
+                logger.local_offset_with(|| Err(IndeterminateOffset));
+                logger.log(&Record::builder().args(format_args!("first")).level(Level::Info).build());
+                logger.log(
+                    &Record::builder()
+                        .args(format_args!("second"))
+                        .level(Level::Info)
+                        .build(),
+                );
+            },
+        );
+
+        assert_eq!(
+            output,
+            concat!(
+                "+02 WARN Could not determine the local UTC offset, using +02:00:00 for timestamps instead\n",
+                "+02 INFO first\n",
+                "+02 INFO second\n",
+            )
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "timestamps")]
+    fn test_local_offset_fallback_level() {
+        let output = capture(
+            SimpleLogger::new()
+                .with_level(LevelFilter::Error)
+                .with_local_timestamps()
+                .with_format_template("{level} {message}"),
+            |logger| {
+                logger.local_offset_with(|| Err(IndeterminateOffset));
+                logger.log(
+                    &Record::builder()
+                        .args(format_args!("failed"))
+                        .level(Level::Error)
+                        .build(),
+                );
+            },
+        );
+
+        assert_eq!(output, "ERROR failed\n");
+    }
+
+    #[test]
+    #[cfg(feature = "timezones")]
+    fn test_with_timezone() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/zoneinfo/Europe/London");
+        let logger = SimpleLogger::new().with_timezone(path.to_str().unwrap());
+        assert!(matches!(logger.timestamps, Timestamps::Timezone(Ok(_))));
+
+        let result = SimpleLogger::new().with_timezone("Nowhere/Nowhere").init();
+        assert!(matches!(result, Err(InitError::Timezone(_))));
+    }
+
+    #[test]
+    fn test_with_error_handler() {
+        let errors = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let logger = SimpleLogger::new().with_error_handler({
+            let errors = errors.clone();
+            move |err| errors.lock().unwrap().push(err.kind())
+        });
+
+        logger.report_error(io::ErrorKind::BrokenPipe.into());
+        logger.report_error(io::ErrorKind::PermissionDenied.into());
+        logger.report_error(io::ErrorKind::Other.into());
+
+        assert_eq!(*errors.lock().unwrap(), vec![io::ErrorKind::PermissionDenied]);
+    }
+
+    #[test]
+    fn test_with_sampling() {
+        let lines = capture(
+            SimpleLogger::new()
+                .with_format_template("{message}")
+                .with_sampling(Level::Debug, 0.5)
+                .with_sampling_seed(1),
+            |logger| {
+                for n in 0..10 {
+                    logger.log(
+                        &Record::builder()
+                            .args(format_args!("{}", n))
+                            .level(Level::Debug)
+                            .build(),
+                    );
+                    logger.log(&Record::builder().args(format_args!("info")).level(Level::Info).build());
+                }
+            },
+        );
+
+        assert_eq!(lines.lines().filter(|line| *line == "info").count(), 10);
+        assert_eq!(
+            lines.lines().filter(|line| *line != "info").collect::<Vec<_>>(),
+            vec!["3", "4", "8"]
+        );
+    }
+
+    #[test]
+    fn test_with_dedup() {
+        let output = capture(
+            SimpleLogger::new()
+                .with_format_template("{level} {message}")
+                .with_dedup(true),
+            |logger| {
+                for message in ["retrying", "retrying", "retrying", "giving up", "giving up"] {
+                    logger.log(
+                        &Record::builder()
+                            .args(format_args!("{}", message))
+                            .level(Level::Warn)
+                            .build(),
+                    );
+                }
+            },
+        );
+
+        assert_eq!(
+            output,
+            "WARN retrying\nWARN last message repeated 2 times\nWARN giving up\nWARN last message repeated 1 times\n"
+        );
+    }
+
     /// > And, without sorting, this would lead to all serde_json logs being treated as if they were configured to
     /// > Error level instead of Trace (since to determine the logging level for target, the code finds first match in
     /// > module_levels by a string prefix).
@@ -694,10 +2486,126 @@ mod test {
         assert_eq!(logger.enabled(&create_log("serde_json", Level::Trace)), true);
     }
 
+    #[test]
+    fn test_directives_level() {
+        let directives = Directives::parse("info");
+        assert_eq!(directives.level, Some(LevelFilter::Info));
+        assert!(directives.module_levels.is_empty());
+    }
+
+    #[test]
+    fn test_directives_module_levels() {
+        let directives = Directives::parse("info, hyper=warn,my_crate::db=trace,chatty,,bad=level");
+        assert_eq!(directives.level, Some(LevelFilter::Info));
+        assert_eq!(
+            directives.module_levels,
+            vec![
+                ("hyper".to_string(), LevelFilter::Warn),
+                ("my_crate::db".to_string(), LevelFilter::Trace),
+                ("chatty".to_string(), LevelFilter::Trace),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_directives_message_filter() {
+        let directives = Directives::parse("info,hyper=warn/req-\\d+/x");
+        assert_eq!(directives.level, Some(LevelFilter::Info));
+        assert_eq!(directives.module_levels, vec![("hyper".to_string(), LevelFilter::Warn)]);
+        assert_eq!(directives.message_filter.as_deref(), Some("req-\\d+/x"));
+
+        let directives = Directives::parse("/req");
+        assert_eq!(directives.level, None);
+        assert_eq!(directives.message_filter.as_deref(), Some("req"));
+    }
+
+    #[test]
+    #[cfg(feature = "regex")]
+    fn test_directives_message_filter_invalid() {
+        assert!(matches!(
+            Directives::parse("info/req-\\d+").message_filter(),
+            Some(Ok(_))
+        ));
+        assert!(matches!(Directives::parse("info/req-(").message_filter(), Some(Err(_))));
+        assert!(Directives::parse("info").message_filter().is_none());
+    }
+
+    #[test]
+    #[cfg(all(unix, feature = "signals"))]
+    fn test_directives_parse_lines() {
+        let directives =
+            Directives::parse_lines("# levels\n\nwarn\n  hyper=info, tokio=off\nmy_crate=debug/req-1\n").unwrap();
+        assert_eq!(directives.level, Some(LevelFilter::Warn));
+        assert_eq!(
+            directives.module_levels,
+            vec![
+                ("hyper".to_string(), LevelFilter::Info),
+                ("tokio".to_string(), LevelFilter::Off),
+                ("my_crate".to_string(), LevelFilter::Debug),
+            ]
+        );
+        assert_eq!(directives.message_filter.as_deref(), Some("req-1"));
+
+        assert_eq!(
+            Directives::parse_lines("my_crate=debug/req-1\nhyper=warn\n").err(),
+            Some(1)
+        );
+        assert_eq!(
+            Directives::parse_lines("info\nmy_crate=debug/req-1\n# done\n").err(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_with_module_level_replaces_target() {
+        let logger = SimpleLogger::new()
+            .with_level(LevelFilter::Off)
+            .with_module_level("my_crate", LevelFilter::Error)
+            .with_module_level("my_crate", LevelFilter::Info);
+
+        assert_eq!(levels::read(&logger.levels).module_levels.len(), 1);
+        assert!(logger.enabled(&create_log("my_crate", Level::Info)));
+    }
+
+    /// Modules only match their own sub-modules, not other crates that share a prefix.
+    #[test]
+    fn test_module_levels_path_boundaries() {
+        let logger = SimpleLogger::new()
+            .with_level(LevelFilter::Off)
+            .with_module_level("serde", LevelFilter::Trace)
+            .with_module_level("my_crate", LevelFilter::Info);
+
+        assert!(logger.enabled(&create_log("serde", Level::Trace)));
+        assert!(logger.enabled(&create_log("serde::de", Level::Trace)));
+        assert!(!logger.enabled(&create_log("serde_json", Level::Error)));
+        assert!(logger.enabled(&create_log("my_crate::db", Level::Info)));
+        assert!(!logger.enabled(&create_log("my_crate_extras", Level::Error)));
+    }
+
+    #[test]
+    fn test_module_prefix_matching() {
+        let logger = SimpleLogger::new()
+            .with_level(LevelFilter::Off)
+            .with_module_level("my_crate", LevelFilter::Info)
+            .with_module_prefix_matching(true);
+
+        assert!(logger.enabled(&create_log("my_crate_extras", Level::Info)));
+        assert!(!logger.enabled(&create_log("my_crate_extras", Level::Debug)));
+    }
+
     fn create_log(name: &str, level: Level) -> Metadata<'_> {
         let mut builder = Metadata::builder();
         builder.level(level);
         builder.target(name);
         builder.build()
     }
+
+    /// Log with a logger writing to a temporary file, and return what was written.
+    fn capture<F: FnOnce(&SimpleLogger)>(logger: SimpleLogger, log: F) -> String {
+        let file = TempFile::new();
+        let logger = logger.with_output_file(&file);
+        log(&logger);
+        logger.flush();
+        file.read()
+    }
 }
@@ -0,0 +1,203 @@
+//! Limiting how often the same call site can log.
+
+use log::{Level, Record};
+use std::{
+    collections::HashMap,
+    sync::{Mutex, PoisonError},
+    time::{Duration, Instant},
+};
+
+/// Allows a number of records per time window from each call site, counting the ones it suppresses.
+///
+/// See [`SimpleLogger::with_rate_limit`](crate::SimpleLogger::with_rate_limit).
+pub(crate) struct RateLimiter {
+    max: usize,
+    window: Duration,
+    state: Mutex<State>,
+}
+
+#[derive(Default)]
+struct State {
+    sites: HashMap<Key, Site>,
+
+    /// The number of sites that have suppressed records since their last summary.
+    pending: usize,
+}
+
+/// A call site, identified by the record's target, file and line.
+#[derive(PartialEq, Eq, Hash)]
+struct Key {
+    target: String,
+    file: Option<String>,
+    line: Option<u32>,
+}
+
+struct Site {
+    window_start: Instant,
+    count: usize,
+    suppressed: usize,
+    level: Level,
+    module_path: Option<String>,
+}
+
+/// A summary of the records suppressed from one call site.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Suppressed {
+    pub(crate) count: usize,
+    pub(crate) level: Level,
+    pub(crate) target: String,
+    pub(crate) module_path: Option<String>,
+    pub(crate) file: Option<String>,
+    pub(crate) line: Option<u32>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(max: usize, window: Duration) -> RateLimiter {
+        RateLimiter {
+            max,
+            window,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Check if a record logged at `now` is allowed.
+    ///
+    /// Also returns summaries for any call sites whose window has closed since they suppressed records, which should
+    /// be logged before the record.
+    pub(crate) fn check(&self, record: &Record, now: Instant) -> (bool, Vec<Suppressed>) {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let State { sites, pending } = &mut *state;
+
+        let mut summaries = Vec::new();
+
+        if *pending > 0 {
+            for (key, site) in sites.iter_mut() {
+                if site.suppressed > 0 && now.duration_since(site.window_start) >= self.window {
+                    summaries.push(site.summarize(key));
+                    *pending -= 1;
+                }
+            }
+        }
+
+        let key = Key {
+            target: record.target().to_string(),
+            file: record.file().map(str::to_string),
+            line: record.line(),
+        };
+
+        let site = sites.entry(key).or_insert_with(|| Site {
+            window_start: now,
+            count: 0,
+            suppressed: 0,
+            level: record.level(),
+            module_path: record.module_path().map(str::to_string),
+        });
+
+        if now.duration_since(site.window_start) >= self.window {
+            site.window_start = now;
+            site.count = 0;
+        }
+
+        if site.count < self.max {
+            site.count += 1;
+            return (true, summaries);
+        }
+
+        if site.suppressed == 0 {
+            *pending += 1;
+        }
+
+        site.suppressed += 1;
+        (false, summaries)
+    }
+
+    /// Summaries for all call sites that have suppressed records since their last summary.
+    pub(crate) fn flush(&self) -> Vec<Suppressed> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        let State { sites, pending } = &mut *state;
+
+        *pending = 0;
+
+        sites
+            .iter_mut()
+            .filter(|(_key, site)| site.suppressed > 0)
+            .map(|(key, site)| site.summarize(key))
+            .collect()
+    }
+}
+
+impl Site {
+    fn summarize(&mut self, key: &Key) -> Suppressed {
+        Suppressed {
+            count: std::mem::take(&mut self.suppressed),
+            level: self.level,
+            target: key.target.clone(),
+            module_path: self.module_path.clone(),
+            file: key.file.clone(),
+            line: key.line,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn check(limiter: &RateLimiter, line: u32, now: Instant) -> (bool, Vec<Suppressed>) {
+        limiter.check(
+            &Record::builder()
+                .args(format_args!("a message"))
+                .level(Level::Warn)
+                .target("my_crate")
+                .file(Some("src/main.rs"))
+                .line(Some(line))
+                .build(),
+            now,
+        )
+    }
+
+    fn suppressed(count: usize, line: u32) -> Suppressed {
+        Suppressed {
+            count,
+            level: Level::Warn,
+            target: "my_crate".to_string(),
+            module_path: None,
+            file: Some("src/main.rs".to_string()),
+            line: Some(line),
+        }
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(1));
+        let start = Instant::now();
+
+        assert_eq!(check(&limiter, 1, start), (true, vec![]));
+        assert_eq!(check(&limiter, 1, start), (true, vec![]));
+        assert_eq!(check(&limiter, 1, start), (false, vec![]));
+        assert_eq!(check(&limiter, 1, start + Duration::from_millis(500)), (false, vec![]));
+
+        // Other call sites have their own limit.
+        assert_eq!(check(&limiter, 2, start), (true, vec![]));
+
+        // The summary is returned by the first record after the window closes, from any call site.
+        let later = start + Duration::from_secs(1);
+        assert_eq!(check(&limiter, 2, later), (true, vec![suppressed(2, 1)]));
+        assert_eq!(check(&limiter, 1, later), (true, vec![]));
+    }
+
+    #[test]
+    fn test_rate_limit_flush() {
+        let limiter = RateLimiter::new(1, Duration::from_secs(60));
+        let start = Instant::now();
+
+        assert_eq!(check(&limiter, 1, start), (true, vec![]));
+        assert_eq!(check(&limiter, 1, start), (false, vec![]));
+        assert_eq!(limiter.flush(), vec![suppressed(1, 1)]);
+        assert_eq!(limiter.flush(), vec![]);
+
+        // Flushing doesn't start a new window.
+        assert_eq!(check(&limiter, 1, start), (false, vec![]));
+        assert_eq!(limiter.flush(), vec![suppressed(1, 1)]);
+    }
+}
@@ -0,0 +1,188 @@
+//! Logging only a fraction of the records at some levels.
+
+use crate::levels::ModuleLevels;
+use log::{Level, Record};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// The increment used by splitmix64.
+const GOLDEN_GAMMA: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Decides which records to keep, using sampling rates for each level and module.
+///
+/// See [`SimpleLogger::with_sampling`](crate::SimpleLogger::with_sampling).
+pub(crate) struct Sampler {
+    /// The rate for each level, indexed by `Level as usize - 1`.
+    levels: [f64; 5],
+
+    /// The rates for specific modules at each level, indexed by `Level as usize - 1`.
+    modules: [ModuleLevels<f64>; 5],
+
+    /// The state of a splitmix64 generator, which can be shared between threads without a lock.
+    state: AtomicU64,
+
+    /// The first rate that wasn't between 0 and 1, which makes `init()` fail.
+    invalid_rate: Option<f64>,
+}
+
+impl Default for Sampler {
+    /// Keeps all records, using a random seed.
+    fn default() -> Self {
+        Sampler {
+            levels: [1.0; 5],
+            modules: Default::default(),
+            state: AtomicU64::new(RandomState::new().build_hasher().finish()),
+            invalid_rate: None,
+        }
+    }
+}
+
+impl Sampler {
+    pub(crate) fn set_level(&mut self, level: Level, rate: f64) {
+        if self.check_rate(rate) {
+            self.levels[level as usize - 1] = rate;
+        }
+    }
+
+    pub(crate) fn set_module_level(&mut self, target: &str, level: Level, rate: f64) {
+        if self.check_rate(rate) {
+            self.modules[level as usize - 1].insert(target, rate);
+        }
+    }
+
+    /// The first rate that wasn't between 0 and 1, if any.
+    pub(crate) fn invalid_rate(&self) -> Option<f64> {
+        self.invalid_rate
+    }
+
+    /// Check a sampling rate is between 0 and 1, remembering it if it's the first one that isn't.
+    fn check_rate(&mut self, rate: f64) -> bool {
+        let valid = (0.0..=1.0).contains(&rate);
+        if !valid && self.invalid_rate.is_none() {
+            self.invalid_rate = Some(rate);
+        }
+        valid
+    }
+
+    pub(crate) fn set_seed(&mut self, seed: u64) {
+        *self.state.get_mut() = seed;
+    }
+
+    /// The sampling rate for a record, from the most specific module with a rate for its level or from its level.
+    ///
+    /// Modules are matched in the same way as module levels, including `prefix_matching`.
+    fn rate(&self, record: &Record, prefix_matching: bool) -> f64 {
+        let index = record.level() as usize - 1;
+
+        self.modules[index]
+            .lookup(record.target(), prefix_matching)
+            .unwrap_or(self.levels[index])
+    }
+
+    /// Check if a record should be kept.
+    ///
+    /// Records with a rate of 1 are always kept without using the generator, so they don't change which sampled
+    /// records are kept.
+    pub(crate) fn keep(&self, record: &Record, prefix_matching: bool) -> bool {
+        let rate = self.rate(record, prefix_matching);
+
+        if rate >= 1.0 {
+            return true;
+        }
+
+        self.next_f64() < rate
+    }
+
+    /// The next number from the generator, between 0 (inclusive) and 1 (exclusive).
+    fn next_f64(&self) -> f64 {
+        let mut z = self
+            .state
+            .fetch_add(GOLDEN_GAMMA, Ordering::Relaxed)
+            .wrapping_add(GOLDEN_GAMMA);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn keep(sampler: &Sampler, target: &str, level: Level) -> bool {
+        keep_with(sampler, target, level, false)
+    }
+
+    fn keep_with(sampler: &Sampler, target: &str, level: Level, prefix_matching: bool) -> bool {
+        sampler.keep(
+            &Record::builder()
+                .args(format_args!("a message"))
+                .level(level)
+                .target(target)
+                .build(),
+            prefix_matching,
+        )
+    }
+
+    #[test]
+    fn test_rate() {
+        let mut sampler = Sampler::default();
+        sampler.set_level(Level::Trace, 0.0);
+        sampler.set_module_level("my_crate", Level::Trace, 1.0);
+        sampler.set_module_level("my_crate::db", Level::Trace, 0.0);
+
+        assert!(keep(&sampler, "other", Level::Debug));
+        assert!(!keep(&sampler, "other", Level::Trace));
+        assert!(keep(&sampler, "my_crate", Level::Trace));
+        assert!(keep(&sampler, "my_crate::http", Level::Trace));
+        assert!(!keep(&sampler, "my_crate::db", Level::Trace));
+        assert!(!keep(&sampler, "my_crate_extras", Level::Trace));
+    }
+
+    #[test]
+    fn test_rate_prefix_matching() {
+        let mut sampler = Sampler::default();
+        sampler.set_module_level("serde", Level::Info, 0.0);
+
+        assert!(keep_with(&sampler, "serde_json", Level::Info, false));
+        assert!(!keep_with(&sampler, "serde_json", Level::Info, true));
+        assert!(!keep_with(&sampler, "serde::de", Level::Info, false));
+    }
+
+    #[test]
+    fn test_rate_nan() {
+        let mut sampler = Sampler::default();
+        sampler.set_level(Level::Debug, f64::NAN);
+        assert!(sampler.invalid_rate().unwrap().is_nan());
+        assert!(keep(&sampler, "my_crate", Level::Debug));
+    }
+
+    #[test]
+    fn test_rate_negative() {
+        let mut sampler = Sampler::default();
+        sampler.set_module_level("my_crate", Level::Debug, -0.5);
+        sampler.set_level(Level::Debug, 2.0);
+        assert_eq!(sampler.invalid_rate(), Some(-0.5));
+    }
+
+    #[test]
+    fn test_seeded() {
+        let sample = || {
+            let mut sampler = Sampler::default();
+            sampler.set_level(Level::Debug, 0.5);
+            sampler.set_seed(42);
+            (0..1000)
+                .map(|_| keep(&sampler, "my_crate", Level::Debug))
+                .collect::<Vec<_>>()
+        };
+
+        let kept = sample();
+        assert_eq!(kept, sample());
+        assert!((400..600).contains(&kept.iter().filter(|keep| **keep).count()));
+    }
+}
@@ -0,0 +1,263 @@
+//! Line formats described by a template string, e.g. `"{timestamp} {level:>5} {target}: {message}"`.
+
+use log::Record;
+use std::{error::Error, fmt};
+
+/// The placeholders that can be used in a template.
+const FIELDS: &[&str] = &[
+    "timestamp",
+    "level",
+    "target",
+    "module_path",
+    "file",
+    "line",
+    "thread",
+    "message",
+];
+
+/// An error in a template string passed to
+/// [`SimpleLogger::with_format_template`](crate::SimpleLogger::with_format_template).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TemplateError {
+    template: String,
+    position: usize,
+    message: String,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid log format template {:?} at position {}: {}",
+            self.template, self.position, self.message
+        )
+    }
+}
+
+impl Error for TemplateError {}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Field {
+    Timestamp,
+    Level,
+    Target,
+    ModulePath,
+    File,
+    Line,
+    Thread,
+    Message,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Align {
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Field { field: Field, align: Align, width: usize },
+}
+
+/// A parsed template.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Template {
+    parts: Vec<Part>,
+}
+
+/// The values a template can refer to, other than those taken directly from the record.
+pub(crate) struct Values<'a> {
+    pub(crate) timestamp: Option<&'a str>,
+    pub(crate) target: &'a str,
+    pub(crate) thread: Option<&'a str>,
+}
+
+impl Template {
+    pub(crate) fn parse(template: &str) -> Result<Template, TemplateError> {
+        let error = |position: usize, message: String| TemplateError {
+            template: template.to_string(),
+            position,
+            message,
+        };
+
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.char_indices().peekable();
+
+        while let Some((position, c)) = chars.next() {
+            match c {
+                '{' if chars.peek().map(|&(_, c)| c) == Some('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek().map(|&(_, c)| c) == Some('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '}' => {
+                    return Err(error(
+                        position,
+                        "unmatched `}` (use `}}` for a literal `}`)".to_string(),
+                    ))
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    loop {
+                        match chars.next() {
+                            Some((_, '}')) => break,
+                            Some((_, c)) => placeholder.push(c),
+                            None => {
+                                return Err(error(position, "unclosed `{` (use `{{` for a literal `{`)".to_string()))
+                            }
+                        }
+                    }
+
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+
+                    parts.push(parse_placeholder(&placeholder).map_err(|message| error(position, message))?);
+                }
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Template { parts })
+    }
+
+    /// Render a line for a record.
+    ///
+    /// `level` is used to style the (already padded) level, e.g. to add colors.
+    pub(crate) fn render(&self, record: &Record, values: &Values, level: &dyn Fn(String) -> String) -> String {
+        let mut line = String::new();
+
+        for part in &self.parts {
+            let (field, align, width) = match part {
+                Part::Literal(literal) => {
+                    line.push_str(literal);
+                    continue;
+                }
+                Part::Field { field, align, width } => (*field, *align, *width),
+            };
+
+            let value = match field {
+                Field::Timestamp => values.timestamp.unwrap_or_default().to_string(),
+                Field::Level => record.level().to_string(),
+                Field::Target => values.target.to_string(),
+                Field::ModulePath => record.module_path().unwrap_or_default().to_string(),
+                Field::File => record.file().unwrap_or_default().to_string(),
+                Field::Line => record.line().map(|line| line.to_string()).unwrap_or_default(),
+                Field::Thread => values.thread.unwrap_or_default().to_string(),
+                Field::Message => record.args().to_string(),
+            };
+
+            let value = match align {
+                Align::Left => format!("{:<width$}", value, width = width),
+                Align::Right => format!("{:>width$}", value, width = width),
+                Align::Center => format!("{:^width$}", value, width = width),
+            };
+
+            if field == Field::Level {
+                line.push_str(&level(value));
+            } else {
+                line.push_str(&value);
+            }
+        }
+
+        line
+    }
+}
+
+/// Parse the inside of a placeholder, e.g. `level:>5`.
+fn parse_placeholder(placeholder: &str) -> Result<Part, String> {
+    let mut split = placeholder.splitn(2, ':');
+    let name = split.next().unwrap_or_default().trim();
+    let spec = split.next();
+
+    let field = match name {
+        "timestamp" => Field::Timestamp,
+        "level" => Field::Level,
+        "target" => Field::Target,
+        "module_path" => Field::ModulePath,
+        "file" => Field::File,
+        "line" => Field::Line,
+        "thread" => Field::Thread,
+        "message" => Field::Message,
+        _ => {
+            return Err(format!(
+                "unknown placeholder `{{{}}}`, expected one of {}",
+                name,
+                FIELDS.join(", ")
+            ))
+        }
+    };
+
+    let (align, width) = match spec {
+        None => (Align::Left, 0),
+        Some(spec) => {
+            let (align, width) = match spec.chars().next() {
+                Some('<') => (Align::Left, &spec[1..]),
+                Some('>') => (Align::Right, &spec[1..]),
+                Some('^') => (Align::Center, &spec[1..]),
+                _ => (Align::Left, spec),
+            };
+
+            let width = width.parse().map_err(|_| {
+                format!(
+                    "invalid format spec `{}` for `{}`, expected an optional alignment (`<`, `>` or `^`) and a width",
+                    spec, name
+                )
+            })?;
+
+            (align, width)
+        }
+    };
+
+    Ok(Part::Field { field, align, width })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use log::Level;
+
+    #[test]
+    fn test_render() {
+        let template =
+            Template::parse("{timestamp} {level:>5} {target}{{{thread}}}: {message} ({file}:{line})").unwrap();
+        let record = Record::builder()
+            .args(format_args!("a message"))
+            .level(Level::Info)
+            .target("my_crate")
+            .file(Some("src/main.rs"))
+            .line(Some(7))
+            .build();
+        let values = Values {
+            timestamp: Some("12:00"),
+            target: "my_crate",
+            thread: Some("main"),
+        };
+
+        assert_eq!(
+            template.render(&record, &values, &|level| format!("[{}]", level)),
+            "12:00 [ INFO] my_crate{main}: a message (src/main.rs:7)"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let err = Template::parse("{level} {nope}").unwrap_err();
+        assert_eq!(err.position, 8);
+        assert!(err.to_string().contains("unknown placeholder `{nope}`"));
+
+        assert!(Template::parse("{level:x}").is_err());
+        assert!(Template::parse("{level").is_err());
+        assert!(Template::parse("level}").is_err());
+    }
+}
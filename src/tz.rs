@@ -0,0 +1,184 @@
+//! Named timezones from the system's zoneinfo database, e.g. `Europe/London`.
+//!
+//! Timezone files (in the TZif format described in [RFC 8536](https://www.rfc-editor.org/rfc/rfc8536)) and POSIX `TZ`
+//! rules are parsed by the [`tz-rs`](https://docs.rs/tz-rs) crate. This module finds the files and turns the offsets
+//! it returns into [`UtcOffset`]s.
+
+use std::{
+    error::Error,
+    fmt,
+    path::{Component, Path, PathBuf},
+};
+use time::UtcOffset;
+use tz_rs::TimeZoneSettings;
+
+/// The directory timezone files are read from if `TZDIR` isn't set.
+const ZONEINFO: &str = "/usr/share/zoneinfo";
+
+/// Names that are always UTC, so they can be used without a zoneinfo database (e.g. in slim containers).
+const UTC_NAMES: &[&str] = &["UTC", "Etc/UTC", "GMT", "Etc/GMT"];
+
+/// An error loading a timezone passed to
+/// [`SimpleLogger::with_timezone`](crate::SimpleLogger::with_timezone).
+///
+/// This type is only available if the `timezones` feature is enabled.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimezoneError {
+    name: String,
+    message: String,
+}
+
+impl fmt::Display for TimezoneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "could not load timezone {:?}: {}", self.name, self.message)
+    }
+}
+
+impl Error for TimezoneError {}
+
+/// The UTC offsets used by a timezone over time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TimeZone(tz_rs::TimeZone);
+
+impl TimeZone {
+    /// Load a timezone by name from the zoneinfo database.
+    ///
+    /// The database is read from `TZDIR`, or `/usr/share/zoneinfo` if it isn't set.
+    pub(crate) fn load(name: &str) -> Result<TimeZone, TimezoneError> {
+        let dir = std::env::var_os("TZDIR")
+            .map(PathBuf::from)
+            .unwrap_or_else(|| PathBuf::from(ZONEINFO));
+
+        TimeZone::load_from(&dir, name)
+    }
+
+    /// Load a timezone by name from a zoneinfo directory.
+    ///
+    /// Like the `TZ` environment variable, `name` can be an absolute path to a timezone file, can start with a `:`,
+    /// or can be a POSIX `TZ` rule (e.g. `EST5EDT,M3.2.0,M11.1.0`) if there is no timezone with that name. `UTC`,
+    /// `Etc/UTC`, `GMT` and `Etc/GMT` don't need a timezone file.
+    fn load_from(dir: &Path, name: &str) -> Result<TimeZone, TimezoneError> {
+        let error = |message: String| TimezoneError {
+            name: name.to_string(),
+            message,
+        };
+
+        let file = name.strip_prefix(':').unwrap_or(name);
+
+        if UTC_NAMES.contains(&file) {
+            return Ok(TimeZone(tz_rs::TimeZone::utc()));
+        }
+
+        if file.is_empty() || Path::new(file).components().any(|c| c == Component::ParentDir) {
+            return Err(error("invalid timezone name".to_string()));
+        }
+
+        let dir = dir
+            .to_str()
+            .ok_or_else(|| error(format!("invalid zoneinfo directory {}", dir.display())))?;
+
+        TimeZoneSettings::new(&[dir], TimeZoneSettings::DEFAULT_READ_FILE_FN)
+            .parse_posix_tz(file)
+            .map(TimeZone)
+            .map_err(|err| error(format!("not found in {} and not a valid TZ rule ({})", dir, err)))
+    }
+
+    /// The UTC offset at a time.
+    pub(crate) fn offset_at(&self, timestamp: i64) -> UtcOffset {
+        let offset = self
+            .0
+            .find_local_time_type(timestamp)
+            .map(|local_time_type| local_time_type.ut_offset())
+            .unwrap_or(0);
+
+        UtcOffset::from_whole_seconds(offset).unwrap_or(UtcOffset::UTC)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use time::{macros::datetime, OffsetDateTime};
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures/zoneinfo")
+    }
+
+    fn offset(tz: &TimeZone, datetime: OffsetDateTime) -> i32 {
+        tz.offset_at(datetime.unix_timestamp()).whole_seconds()
+    }
+
+    #[test]
+    fn test_london() {
+        let tz = TimeZone::load_from(&fixtures(), "Europe/London").unwrap();
+
+        assert_eq!(offset(&tz, datetime!(1800-01-01 00:00 UTC)), -75);
+        assert_eq!(offset(&tz, datetime!(1970-01-01 00:00 UTC)), 3600);
+        assert_eq!(offset(&tz, datetime!(2026-01-15 12:00 UTC)), 0);
+        assert_eq!(offset(&tz, datetime!(2026-03-29 00:59:59 UTC)), 0);
+        assert_eq!(offset(&tz, datetime!(2026-03-29 01:00 UTC)), 3600);
+        assert_eq!(offset(&tz, datetime!(2026-10-25 00:59:59 UTC)), 3600);
+        assert_eq!(offset(&tz, datetime!(2026-10-25 01:00 UTC)), 0);
+
+        // After the last transition in the file, the rule in the footer is used.
+        assert_eq!(offset(&tz, datetime!(2100-03-28 00:59:59 UTC)), 0);
+        assert_eq!(offset(&tz, datetime!(2100-03-28 01:00 UTC)), 3600);
+        assert_eq!(offset(&tz, datetime!(2100-10-31 00:59:59 UTC)), 3600);
+        assert_eq!(offset(&tz, datetime!(2100-10-31 01:00 UTC)), 0);
+    }
+
+    #[test]
+    fn test_sydney() {
+        let tz = TimeZone::load_from(&fixtures(), "Australia/Sydney").unwrap();
+
+        assert_eq!(offset(&tz, datetime!(2026-01-15 12:00 UTC)), 11 * 3600);
+        assert_eq!(offset(&tz, datetime!(2026-07-15 12:00 UTC)), 10 * 3600);
+        assert_eq!(offset(&tz, datetime!(2090-04-01 15:59:59 UTC)), 11 * 3600);
+        assert_eq!(offset(&tz, datetime!(2090-04-01 16:00 UTC)), 10 * 3600);
+        assert_eq!(offset(&tz, datetime!(2090-09-30 15:59:59 UTC)), 10 * 3600);
+        assert_eq!(offset(&tz, datetime!(2090-09-30 16:00 UTC)), 11 * 3600);
+        assert_eq!(offset(&tz, datetime!(2090-12-31 13:00 UTC)), 11 * 3600);
+    }
+
+    #[test]
+    fn test_slim() {
+        // A version 2 file with no transitions, only a footer.
+        let tz = TimeZone::load_from(&fixtures(), "Slim/London").unwrap();
+
+        assert_eq!(offset(&tz, datetime!(2026-01-15 12:00 UTC)), 0);
+        assert_eq!(offset(&tz, datetime!(2026-07-15 12:00 UTC)), 3600);
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let err = TimeZone::load_from(&fixtures(), "Europe/Nowhere").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("could not load timezone \"Europe/Nowhere\": "));
+
+        assert!(TimeZone::load_from(&fixtures(), "../fixtures/zoneinfo/Europe/London").is_err());
+        assert!(TimeZone::load_from(&fixtures(), "").is_err());
+    }
+
+    #[test]
+    fn test_load_utc_without_zoneinfo() {
+        let empty = fixtures().join("missing");
+
+        for name in ["UTC", "Etc/UTC", "GMT", "Etc/GMT", ":UTC"] {
+            let tz = TimeZone::load_from(&empty, name).unwrap();
+            assert_eq!(offset(&tz, datetime!(2026-07-15 12:00 UTC)), 0, "{}", name);
+        }
+
+        assert!(TimeZone::load_from(&empty, "Europe/London").is_err());
+    }
+
+    #[test]
+    fn test_load_rule() {
+        let tz = TimeZone::load_from(&fixtures(), ":EST5EDT,M3.2.0,M11.1.0").unwrap();
+        assert_eq!(offset(&tz, datetime!(2026-01-15 12:00 UTC)), -5 * 3600);
+        assert_eq!(offset(&tz, datetime!(2026-03-08 06:59:59 UTC)), -5 * 3600);
+        assert_eq!(offset(&tz, datetime!(2026-03-08 07:00 UTC)), -4 * 3600);
+        assert_eq!(offset(&tz, datetime!(2026-11-01 05:59:59 UTC)), -4 * 3600);
+        assert_eq!(offset(&tz, datetime!(2026-11-01 06:00 UTC)), -5 * 3600);
+    }
+}